@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use crate::{Direction, Position, Region};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    pub data: Vec<Vec<T>>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(data: Vec<Vec<T>>) -> Self {
+        Self { data }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.data.first().map(|row| row.len()).unwrap_or_default()
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn get(&self, pos: &Position) -> Option<&T> {
+        if !self.is_within_bounds(pos) {
+            return None;
+        }
+
+        self.data[pos.y as usize].get(pos.x as usize)
+    }
+
+    pub fn is_within_bounds(&self, pos: &Position) -> bool {
+        pos.x >= 0
+            && pos.x < self.get_width() as isize
+            && pos.y >= 0
+            && pos.y < self.get_height() as isize
+    }
+
+    pub fn get_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.data.iter().enumerate().flat_map(|(y, row)| {
+            (0..row.len()).map(move |x| Position {
+                x: x as isize,
+                y: y as isize,
+            })
+        })
+    }
+}
+
+impl<T: Clone + PartialEq> Grid<T> {
+    // Splits the whole grid into groups of connected positions sharing the same item
+    pub fn get_regions(&self) -> Vec<Region<T>> {
+        self.get_regions_by(&Direction::generate_basic_directions_list(), |a, b| a == b)
+    }
+
+    pub fn get_region(&self, initial_pos: Position) -> Region<T> {
+        self.get_region_by(
+            initial_pos,
+            &Direction::generate_basic_directions_list(),
+            |a, b| a == b,
+        )
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    // Regions spread through the given directions to every item considered the same as
+    // the one at their first position, which becomes the region id. Adjacency goes both
+    // ways, so regions also spread through the opposite of every direction
+    pub fn get_regions_by(
+        &self,
+        directions: &[Direction],
+        is_same: impl Fn(&T, &T) -> bool,
+    ) -> Vec<Region<T>> {
+        let mut regions = Vec::new();
+        let mut visited_pos = HashSet::new();
+
+        for position in self.get_positions() {
+            if visited_pos.contains(&position) {
+                continue;
+            }
+
+            let region = self.get_region_by(position, directions, &is_same);
+
+            visited_pos.extend(region.items.clone());
+
+            regions.push(region);
+        }
+
+        regions
+    }
+
+    pub fn get_region_by(
+        &self,
+        initial_pos: Position,
+        directions: &[Direction],
+        is_same: impl Fn(&T, &T) -> bool,
+    ) -> Region<T> {
+        let region_id = self
+            .get(&initial_pos)
+            .expect("Initial position must be within bounds")
+            .clone();
+        let mut region_items = HashSet::new();
+
+        let directions: Vec<Direction> = directions
+            .iter()
+            .flat_map(|direction| [direction.clone(), Direction::apply_180_rotation(direction)])
+            .collect();
+
+        let mut next_positions = vec![initial_pos];
+
+        while let Some(next_pos) = next_positions.pop() {
+            if !self
+                .get(&next_pos)
+                .is_some_and(|item| is_same(&region_id, item))
+            {
+                continue;
+            }
+
+            region_items.insert(next_pos.clone());
+
+            for direction in directions.iter() {
+                let new_pos = Direction::apply_offset(direction, next_pos.x, next_pos.y);
+
+                if region_items.contains(&new_pos) {
+                    continue;
+                }
+
+                next_positions.push(new_pos);
+            }
+        }
+
+        Region {
+            id: region_id,
+            items: region_items,
+        }
+    }
+}
@@ -1,3 +1,9 @@
+mod grid;
+mod region;
+
+pub use grid::Grid;
+pub use region::Region;
+
 #[derive(Debug, Clone)]
 pub enum Direction {
     Top,
@@ -74,4 +80,8 @@ impl Direction {
             Direction::TopLeft => Direction::TopRight,
         }
     }
+
+    pub fn apply_180_rotation(direction: &Direction) -> Direction {
+        Self::apply_90_clockwise_rotation(&Self::apply_90_clockwise_rotation(direction))
+    }
 }
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Direction, Position};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region<T> {
+    pub id: T,
+    pub items: HashSet<Position>,
+}
+
+impl<T> Region<T> {
+    pub fn get_area(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn get_perimeter(&self) -> usize {
+        count_perimeter(&self.items)
+    }
+
+    // A polygon has as many sides as corners
+    pub fn get_sides(&self) -> usize {
+        count_corners(&self.items)
+    }
+
+    // Groups of positions out of the region that are fully surrounded by it
+    pub fn get_holes(&self) -> Vec<HashSet<Position>> {
+        let Some((min, max)) = self.get_bounds() else {
+            return Vec::new();
+        };
+
+        // Anything reachable from the margin around the region is outside of it
+        let min = Position {
+            x: min.x - 1,
+            y: min.y - 1,
+        };
+        let max = Position {
+            x: max.x + 1,
+            y: max.y + 1,
+        };
+
+        let is_within_margin =
+            |pos: &Position| pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y;
+
+        let mut outside = HashSet::new();
+        let mut next_positions = vec![min.clone()];
+
+        while let Some(next_pos) = next_positions.pop() {
+            if !is_within_margin(&next_pos)
+                || self.items.contains(&next_pos)
+                || !outside.insert(next_pos.clone())
+            {
+                continue;
+            }
+
+            for direction in Direction::generate_basic_directions_list().iter() {
+                next_positions.push(Direction::apply_offset(direction, next_pos.x, next_pos.y));
+            }
+        }
+
+        let mut holes: Vec<HashSet<Position>> = Vec::new();
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let position = Position { x, y };
+
+                if self.items.contains(&position)
+                    || outside.contains(&position)
+                    || holes.iter().any(|hole| hole.contains(&position))
+                {
+                    continue;
+                }
+
+                let mut hole = HashSet::new();
+                let mut next_positions = vec![position];
+
+                while let Some(next_pos) = next_positions.pop() {
+                    if self.items.contains(&next_pos) || !hole.insert(next_pos.clone()) {
+                        continue;
+                    }
+
+                    for direction in Direction::generate_basic_directions_list().iter() {
+                        next_positions
+                            .push(Direction::apply_offset(direction, next_pos.x, next_pos.y));
+                    }
+                }
+
+                holes.push(hole);
+            }
+        }
+
+        holes
+    }
+
+    // Holes are only bordered by the region, so their fences are the inner ones
+    pub fn get_inner_perimeter(&self) -> usize {
+        self.get_holes().iter().map(count_perimeter).sum()
+    }
+
+    pub fn get_outer_perimeter(&self) -> usize {
+        self.get_perimeter() - self.get_inner_perimeter()
+    }
+
+    pub fn get_inner_sides(&self) -> usize {
+        self.get_holes().iter().map(count_corners).sum()
+    }
+
+    pub fn get_outer_sides(&self) -> usize {
+        self.get_sides() - self.get_inner_sides()
+    }
+
+    // Whether the other region lies completely within the holes of this one
+    pub fn encloses<U>(&self, other: &Region<U>) -> bool {
+        let holes = self.get_holes();
+
+        !other.items.is_empty()
+            && other
+                .items
+                .iter()
+                .all(|item| holes.iter().any(|hole| hole.contains(item)))
+    }
+
+    // Straight fence lines as their start and end corners, where every position
+    // spans from its own coordinates up to the next ones on both axes
+    pub fn get_fence_segments(&self) -> Vec<(Position, Position)> {
+        let mut walls: HashMap<(usize, bool, isize), Vec<isize>> = HashMap::new();
+
+        for item in self.items.iter() {
+            for (index, direction) in Direction::generate_basic_directions_list()
+                .iter()
+                .enumerate()
+            {
+                let new_pos = Direction::apply_offset(direction, item.x, item.y);
+
+                if self.items.contains(&new_pos) {
+                    continue;
+                }
+
+                // Walls facing different sides never merge, even within the same line
+                let (is_horizontal, line, offset) = match direction {
+                    Direction::Top => (true, item.y, item.x),
+                    Direction::Bottom => (true, item.y + 1, item.x),
+                    Direction::Left => (false, item.x, item.y),
+                    Direction::Right => (false, item.x + 1, item.y),
+                    _ => unreachable!(),
+                };
+
+                walls
+                    .entry((index, is_horizontal, line))
+                    .or_default()
+                    .push(offset);
+            }
+        }
+
+        let mut segments = Vec::new();
+
+        for ((_, is_horizontal, line), mut offsets) in walls {
+            offsets.sort();
+
+            let mut start = offsets[0];
+
+            for (i, offset) in offsets.iter().enumerate() {
+                let is_last = offsets.get(i + 1).is_none_or(|next| next - offset > 1);
+
+                if !is_last {
+                    continue;
+                }
+
+                segments.push(match is_horizontal {
+                    true => (
+                        Position { x: start, y: line },
+                        Position {
+                            x: offset + 1,
+                            y: line,
+                        },
+                    ),
+                    false => (
+                        Position { x: line, y: start },
+                        Position {
+                            x: line,
+                            y: offset + 1,
+                        },
+                    ),
+                });
+
+                if let Some(next) = offsets.get(i + 1) {
+                    start = *next;
+                }
+            }
+        }
+
+        segments.sort_by_key(|(start, end)| (start.y, start.x, end.y, end.x));
+
+        segments
+    }
+
+    // Top left and bottom right positions of the region, both inclusive
+    pub fn get_bounds(&self) -> Option<(Position, Position)> {
+        let min_x = self.items.iter().map(|item| item.x).min()?;
+        let max_x = self.items.iter().map(|item| item.x).max()?;
+        let min_y = self.items.iter().map(|item| item.y).min()?;
+        let max_y = self.items.iter().map(|item| item.y).max()?;
+
+        Some((
+            Position { x: min_x, y: min_y },
+            Position { x: max_x, y: max_y },
+        ))
+    }
+}
+
+fn count_perimeter(items: &HashSet<Position>) -> usize {
+    let directions = Direction::generate_basic_directions_list();
+
+    items
+        .iter()
+        .map(|item| {
+            directions
+                .iter()
+                .filter(|direction| {
+                    !items.contains(&Direction::apply_offset(direction, item.x, item.y))
+                })
+                .count()
+        })
+        .sum()
+}
+
+fn count_corners(items: &HashSet<Position>) -> usize {
+    // Every pair of contiguous sides of a position, along with the diagonal between them
+    let corners = [
+        (Direction::Top, Direction::Right, Direction::TopRight),
+        (Direction::Right, Direction::Bottom, Direction::BottomRight),
+        (Direction::Bottom, Direction::Left, Direction::BottomLeft),
+        (Direction::Left, Direction::Top, Direction::TopLeft),
+    ];
+
+    items
+        .iter()
+        .map(|item| {
+            corners
+                .iter()
+                .filter(|(side_a, side_b, diagonal)| {
+                    let has_side_a =
+                        items.contains(&Direction::apply_offset(side_a, item.x, item.y));
+                    let has_side_b =
+                        items.contains(&Direction::apply_offset(side_b, item.x, item.y));
+                    let has_diagonal =
+                        items.contains(&Direction::apply_offset(diagonal, item.x, item.y));
+
+                    // Either an outer corner or an inner one
+                    (!has_side_a && !has_side_b) || (has_side_a && has_side_b && !has_diagonal)
+                })
+                .count()
+        })
+        .sum()
+}
@@ -1,14 +1,116 @@
 use common::{Direction, Position};
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
 };
 
+const IMPASSABLE_TILE: char = '.';
+#[allow(dead_code)]
+const TRAIL_MARKER: char = '#';
+
+// Bitset with the tops that can be reached from a position
+#[derive(Clone, Debug, PartialEq)]
+struct TopsSet(Vec<u64>);
+
+impl TopsSet {
+    fn new(size: usize) -> Self {
+        Self(vec![0; size.div_ceil(64)])
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        for (block, other_block) in self.0.iter_mut().zip(other.0.iter()) {
+            *block |= other_block;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(block_index, block)| {
+            (0..64)
+                .filter(move |bit| block & (1 << bit) != 0)
+                .map(move |bit| block_index * 64 + bit)
+        })
+    }
+}
+
+// For every position, the number of trails and the set of tops reachable from it
+struct TrailsTable {
+    ratings: Vec<Vec<u64>>,
+    tops: Vec<Vec<TopsSet>>,
+    tops_positions: Vec<Position>,
+}
+
+#[derive(Debug, Clone)]
+struct TrailRules {
+    start_height: u32,
+    end_height: u32,
+    steps: Vec<i64>,
+    directions: Vec<Direction>,
+}
+
+impl Default for TrailRules {
+    fn default() -> Self {
+        Self {
+            start_height: 0,
+            end_height: 9,
+            steps: vec![1],
+            directions: Direction::generate_basic_directions_list().to_vec(),
+        }
+    }
+}
+
+impl TrailRules {
+    #[allow(dead_code)]
+    fn with_heights(mut self, start_height: u32, end_height: u32) -> Self {
+        self.start_height = start_height;
+        self.end_height = end_height;
+        self
+    }
+
+    #[allow(dead_code)]
+    fn with_steps(mut self, steps: Vec<i64>) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    // Allows climbing any height between 1 and max_step at once
+    #[allow(dead_code)]
+    fn with_steps_up_to(self, max_step: i64) -> Self {
+        self.with_steps((1..=max_step).collect())
+    }
+
+    #[allow(dead_code)]
+    fn with_directions(mut self, directions: Vec<Direction>) -> Self {
+        self.directions = directions;
+        self
+    }
+
+    // When every step goes the same way, trails can't loop back to an already visited position
+    fn is_monotonic(&self) -> bool {
+        self.steps.iter().all(|step| *step > 0) || self.steps.iter().all(|step| *step < 0)
+    }
+
+    fn is_ascending(&self) -> bool {
+        self.steps.iter().all(|step| *step > 0)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct TrailheadSummary {
+    trailhead: Position,
+    tops: Vec<Position>,
+    rating: u64,
+}
+
 #[derive(Debug, PartialEq)]
 struct TopographicMap {
-    data: Vec<Vec<u32>>,
+    // Impassable positions are empty
+    data: Vec<Vec<Option<u32>>>,
 }
 
 impl TopographicMap {
@@ -16,13 +118,16 @@ impl TopographicMap {
         let file = File::open(file_path).expect("File can't be read");
         let reader = BufReader::new(file);
 
-        let data: Vec<Vec<u32>> = reader
+        let data: Vec<Vec<Option<u32>>> = reader
             .lines()
             .map(|line| {
                 line.unwrap()
                     .trim()
                     .chars()
-                    .map(|x| x.to_digit(10).expect("Can't convert char to digit"))
+                    .map(|x| match x {
+                        IMPASSABLE_TILE => None,
+                        _ => Some(x.to_digit(10).expect("Can't convert char to digit")),
+                    })
                     .collect()
             })
             .collect();
@@ -30,14 +135,22 @@ impl TopographicMap {
         Self { data }
     }
 
-    fn find_trailheads(&self) -> Vec<Position> {
+    fn get_height(&self, position: &Position) -> Option<u32> {
+        if !self.is_within_bounds(position.x, position.y) {
+            return None;
+        }
+
+        self.data[position.y as usize][position.x as usize]
+    }
+
+    fn find_positions_with_height(&self, height: u32) -> Vec<Position> {
         self.data
             .iter()
             .enumerate()
             .flat_map(|(y, line)| {
                 line.iter()
                     .enumerate()
-                    .filter(|(_, item)| **item == 0)
+                    .filter(|(_, item)| **item == Some(height))
                     .map(|(x, _)| Position {
                         x: x as isize,
                         y: y as isize,
@@ -47,63 +160,267 @@ impl TopographicMap {
             .collect()
     }
 
-    fn get_trailheads_tops(&self, trailhead: &Position) -> Vec<Position> {
-        let directions = Direction::generate_basic_directions_list();
+    fn find_trailheads(&self, rules: &TrailRules) -> Vec<Position> {
+        self.find_positions_with_height(rules.start_height)
+    }
 
-        // The same top can be reached from several paths
-        let mut trailhead_tops = Vec::new();
+    fn get_next_positions(&self, position: &Position, rules: &TrailRules) -> Vec<Position> {
+        let Some(height) = self.get_height(position) else {
+            return Vec::new();
+        };
 
-        // Get a position, generate possible new ones, queue if valid, and start again
-        let mut queued_next_pos = vec![trailhead.clone()];
-        while let Some(current_pos) = queued_next_pos.pop() {
-            let current_pos_value = self.data[current_pos.y as usize][current_pos.x as usize];
+        // Trails finish as soon as they reach the end height
+        if height == rules.end_height {
+            return Vec::new();
+        }
 
-            for direction in directions.iter() {
-                let next_pos = Direction::apply_offset(direction, current_pos.x, current_pos.y);
+        rules
+            .directions
+            .iter()
+            .map(|direction| Direction::apply_offset(direction, position.x, position.y))
+            .filter(|next_pos| {
+                self.get_height(next_pos).is_some_and(|next_height| {
+                    rules.steps.contains(&(next_height as i64 - height as i64))
+                })
+            })
+            .collect()
+    }
 
-                if !self.is_within_bounds(next_pos.x, next_pos.y) {
-                    continue;
-                }
+    fn get_trails_table(&self, rules: &TrailRules) -> TrailsTable {
+        let height = self.data.len();
+        let width = self.data[0].len();
 
-                let next_pos_value = self.data[next_pos.y as usize][next_pos.x as usize];
+        // Every top gets an index within the reachable tops sets
+        let mut tops_count = 0;
+        let mut positions = Vec::new();
 
-                // Needs to be increasing one by one
-                if next_pos_value != current_pos_value + 1 {
+        for (y, line) in self.data.iter().enumerate() {
+            for (x, value) in line.iter().enumerate() {
+                let Some(value) = value else {
                     continue;
-                }
+                };
+
+                positions.push((
+                    *value,
+                    Position {
+                        x: x as isize,
+                        y: y as isize,
+                    },
+                ));
 
-                if next_pos_value == 9 {
-                    trailhead_tops.push(next_pos.clone());
-                } else {
-                    queued_next_pos.push(next_pos.clone());
+                if *value == rules.end_height {
+                    tops_count += 1;
                 }
             }
         }
 
-        trailhead_tops
+        // Positions closer to the tops are solved first,
+        // so every position only depends on already solved ones
+        positions.sort_by_key(|(value, _)| *value);
+
+        if rules.is_ascending() {
+            positions.reverse();
+        }
+
+        let mut table = TrailsTable {
+            ratings: vec![vec![0; width]; height],
+            tops: vec![vec![TopsSet::new(tops_count); width]; height],
+            tops_positions: Vec::with_capacity(tops_count),
+        };
+
+        for (value, position) in positions.iter() {
+            let (x, y) = (position.x as usize, position.y as usize);
+
+            if *value == rules.end_height {
+                table.ratings[y][x] = 1;
+                table.tops[y][x].insert(table.tops_positions.len());
+                table.tops_positions.push(position.clone());
+                continue;
+            }
+
+            for next_pos in self.get_next_positions(position, rules) {
+                let (next_x, next_y) = (next_pos.x as usize, next_pos.y as usize);
+
+                table.ratings[y][x] += table.ratings[next_y][next_x];
+
+                let next_tops = table.tops[next_y][next_x].clone();
+                table.tops[y][x].union_with(&next_tops);
+            }
+        }
+
+        table
     }
 
-    fn calculate_trailheads_total_score(&self) -> u32 {
-        self.find_trailheads()
-            .iter()
+    // Walks every trail that doesn't visit a position twice, needed when steps can go both ways
+    // Returns whether the walk should go on after the given trail
+    fn visit_trails(
+        &self,
+        trail: &mut Vec<Position>,
+        visited: &mut HashSet<Position>,
+        rules: &TrailRules,
+        on_trail: &mut dyn FnMut(&[Position]) -> bool,
+    ) -> bool {
+        let position = trail.last().expect("Trails can't be empty").clone();
+
+        if self.get_height(&position) == Some(rules.end_height) {
+            return on_trail(trail);
+        }
+
+        visited.insert(position.clone());
+
+        let mut keep_walking = true;
+
+        for next_pos in self.get_next_positions(&position, rules) {
+            if visited.contains(&next_pos) {
+                continue;
+            }
+
+            trail.push(next_pos);
+            keep_walking = self.visit_trails(trail, visited, rules, on_trail);
+            trail.pop();
+
+            if !keep_walking {
+                break;
+            }
+        }
+
+        visited.remove(&position);
+
+        keep_walking
+    }
+
+    #[allow(dead_code)]
+    fn get_trails(
+        &self,
+        trailhead: &Position,
+        rules: &TrailRules,
+        limit: Option<usize>,
+    ) -> Vec<Vec<Position>> {
+        let mut trails = Vec::new();
+
+        if limit == Some(0) || self.get_height(trailhead) != Some(rules.start_height) {
+            return trails;
+        }
+
+        self.visit_trails(
+            &mut vec![trailhead.clone()],
+            &mut HashSet::new(),
+            rules,
+            &mut |trail| {
+                trails.push(trail.to_vec());
+                limit.is_none_or(|limit| trails.len() < limit)
+            },
+        );
+
+        trails
+    }
+
+    fn get_trailheads_summaries(&self, rules: &TrailRules) -> Vec<TrailheadSummary> {
+        let trailheads = self.find_trailheads(rules);
+
+        if !rules.is_monotonic() {
+            return trailheads
+                .into_iter()
+                .map(|trailhead| {
+                    let mut tops = HashSet::new();
+                    let mut rating = 0;
+
+                    self.visit_trails(
+                        &mut vec![trailhead.clone()],
+                        &mut HashSet::new(),
+                        rules,
+                        &mut |trail| {
+                            tops.insert(trail[trail.len() - 1].clone());
+                            rating += 1;
+                            true
+                        },
+                    );
+
+                    TrailheadSummary {
+                        trailhead,
+                        tops: tops.into_iter().collect(),
+                        rating,
+                    }
+                })
+                .collect();
+        }
+
+        let table = self.get_trails_table(rules);
+
+        trailheads
+            .into_iter()
             .map(|trailhead| {
-                let mut unique_trailheads = HashSet::new();
+                let (x, y) = (trailhead.x as usize, trailhead.y as usize);
+
+                TrailheadSummary {
+                    tops: table.tops[y][x]
+                        .iter()
+                        .map(|index| table.tops_positions[index].clone())
+                        .collect(),
+                    rating: table.ratings[y][x],
+                    trailhead,
+                }
+            })
+            .collect()
+    }
 
-                self.get_trailheads_tops(trailhead)
-                    .into_iter()
-                    .for_each(|top| {
-                        unique_trailheads.insert(top);
-                    });
+    // For every top, the trailheads with at least one trail reaching it
+    #[allow(dead_code)]
+    fn get_tops_trailheads(&self, rules: &TrailRules) -> HashMap<Position, Vec<Position>> {
+        let mut tops_trailheads: HashMap<Position, Vec<Position>> = HashMap::new();
+
+        for summary in self.get_trailheads_summaries(rules) {
+            for top in summary.tops {
+                tops_trailheads
+                    .entry(top)
+                    .or_default()
+                    .push(summary.trailhead.clone());
+            }
+        }
+
+        tops_trailheads
+    }
 
-                unique_trailheads.len() as u32
+    #[allow(dead_code)]
+    fn render_trail(&self, trail: &[Position], marker: char) -> String {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(y, line)| {
+                line.iter()
+                    .enumerate()
+                    .map(|(x, value)| {
+                        let position = Position {
+                            x: x as isize,
+                            y: y as isize,
+                        };
+
+                        if trail.contains(&position) {
+                            return marker;
+                        }
+
+                        match value {
+                            Some(value) => char::from_digit(*value, 10).unwrap_or('?'),
+                            None => IMPASSABLE_TILE,
+                        }
+                    })
+                    .collect::<String>()
             })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn calculate_trailheads_total_score(&self, rules: &TrailRules) -> u64 {
+        self.get_trailheads_summaries(rules)
+            .iter()
+            .map(|summary| summary.tops.len() as u64)
             .sum()
     }
 
-    fn calculate_trailheads_total_rating(&self) -> u32 {
-        self.find_trailheads()
+    fn calculate_trailheads_total_rating(&self, rules: &TrailRules) -> u64 {
+        self.get_trailheads_summaries(rules)
             .iter()
-            .map(|trailhead| self.get_trailheads_tops(trailhead).len() as u32)
+            .map(|summary| summary.rating)
             .sum()
     }
 
@@ -115,11 +432,13 @@ impl TopographicMap {
 fn main() {
     let topographic_map = TopographicMap::parse("day10/data/input.txt");
 
-    let trailheads_score = topographic_map.calculate_trailheads_total_score();
+    let rules = TrailRules::default();
+
+    let trailheads_score = topographic_map.calculate_trailheads_total_score(&rules);
 
     println!("Part 1 result: {}", trailheads_score);
 
-    let trailheads_rating = topographic_map.calculate_trailheads_total_rating();
+    let trailheads_rating = topographic_map.calculate_trailheads_total_rating(&rules);
 
     println!("Part 2 result: {}", trailheads_rating);
 }
@@ -130,17 +449,22 @@ mod tests {
     use super::*;
 
     fn get_test_input() -> TopographicMap {
+        let data: Vec<Vec<u32>> = vec![
+            vec![8, 9, 0, 1, 0, 1, 2, 3],
+            vec![7, 8, 1, 2, 1, 8, 7, 4],
+            vec![8, 7, 4, 3, 0, 9, 6, 5],
+            vec![9, 6, 5, 4, 9, 8, 7, 4],
+            vec![4, 5, 6, 7, 8, 9, 0, 3],
+            vec![3, 2, 0, 1, 9, 0, 1, 2],
+            vec![0, 1, 3, 2, 9, 8, 0, 1],
+            vec![1, 0, 4, 5, 6, 7, 3, 2],
+        ];
+
         TopographicMap {
-            data: vec![
-                vec![8, 9, 0, 1, 0, 1, 2, 3],
-                vec![7, 8, 1, 2, 1, 8, 7, 4],
-                vec![8, 7, 4, 3, 0, 9, 6, 5],
-                vec![9, 6, 5, 4, 9, 8, 7, 4],
-                vec![4, 5, 6, 7, 8, 9, 0, 3],
-                vec![3, 2, 0, 1, 9, 0, 1, 2],
-                vec![0, 1, 3, 2, 9, 8, 0, 1],
-                vec![1, 0, 4, 5, 6, 7, 3, 2],
-            ],
+            data: data
+                .into_iter()
+                .map(|line| line.into_iter().map(Some).collect())
+                .collect(),
         }
     }
 
@@ -157,13 +481,172 @@ mod tests {
     fn check_trailheads_total_score() {
         let test_data = get_test_input();
 
-        assert_eq!(test_data.calculate_trailheads_total_score(), 36);
+        assert_eq!(
+            test_data.calculate_trailheads_total_score(&TrailRules::default()),
+            36
+        );
     }
 
     #[test]
     fn check_trailheads_total_rating() {
         let test_data = get_test_input();
 
-        assert_eq!(test_data.calculate_trailheads_total_rating(), 81);
+        assert_eq!(
+            test_data.calculate_trailheads_total_rating(&TrailRules::default()),
+            81
+        );
+    }
+
+    #[test]
+    fn check_diagonal_trails() {
+        // Every trail from the corner is a monotone path to the first diagonal of tops
+        let test_data = TopographicMap {
+            data: (0..10)
+                .map(|y| (0..10).map(|x| Some((x + y).min(9))).collect())
+                .collect(),
+        };
+        let rules = TrailRules::default();
+
+        assert_eq!(test_data.calculate_trailheads_total_score(&rules), 10);
+        assert_eq!(test_data.calculate_trailheads_total_rating(&rules), 512);
+    }
+
+    #[test]
+    fn check_impassable_trails() {
+        let test_data = TopographicMap::parse("data/test_impassable.txt");
+        let rules = TrailRules::default();
+
+        assert_eq!(test_data.data[4][1], None);
+        assert_eq!(test_data.calculate_trailheads_total_score(&rules), 2);
+        assert_eq!(test_data.calculate_trailheads_total_rating(&rules), 2);
+    }
+
+    #[test]
+    fn check_descending_trails() {
+        // Walking down from every top finds the same trails backwards
+        let test_data = get_test_input();
+        let rules = TrailRules::default()
+            .with_heights(9, 0)
+            .with_steps(vec![-1]);
+
+        assert_eq!(test_data.calculate_trailheads_total_score(&rules), 36);
+        assert_eq!(test_data.calculate_trailheads_total_rating(&rules), 81);
+    }
+
+    #[test]
+    fn check_custom_steps_trails() {
+        let test_data = get_test_input();
+        let rules = TrailRules::default().with_steps_up_to(9);
+
+        assert_eq!(test_data.calculate_trailheads_total_score(&rules), 47);
+        assert_eq!(test_data.calculate_trailheads_total_rating(&rules), 246);
+
+        let test_data = TopographicMap {
+            data: vec![
+                vec![Some(0), Some(1), Some(2)],
+                vec![Some(1), Some(2), Some(1)],
+            ],
+        };
+        let rules = TrailRules::default()
+            .with_heights(0, 2)
+            .with_steps(vec![-1, 1]);
+
+        assert_eq!(test_data.calculate_trailheads_total_score(&rules), 2);
+        assert_eq!(test_data.calculate_trailheads_total_rating(&rules), 3);
+    }
+
+    #[test]
+    fn check_diagonal_directions_trails() {
+        let test_data = TopographicMap {
+            data: vec![vec![Some(0), None, Some(2)], vec![None, Some(1), None]],
+        };
+        let rules = TrailRules::default().with_heights(0, 2);
+
+        assert_eq!(test_data.calculate_trailheads_total_score(&rules), 0);
+
+        let rules = rules.with_directions(Direction::generate_directions_list().to_vec());
+
+        assert_eq!(test_data.calculate_trailheads_total_score(&rules), 1);
+        assert_eq!(test_data.calculate_trailheads_total_rating(&rules), 1);
+    }
+
+    #[test]
+    fn check_trails_listing() {
+        let test_data = TopographicMap::parse("data/test_impassable.txt");
+        let rules = TrailRules::default();
+        let trailhead = Position { x: 3, y: 0 };
+
+        let trails = test_data.get_trails(&trailhead, &rules, None);
+
+        assert_eq!(trails.len(), 2);
+        assert!(trails.iter().all(|trail| trail.len() == 10));
+        assert!(trails.contains(&vec![
+            Position { x: 3, y: 0 },
+            Position { x: 3, y: 1 },
+            Position { x: 3, y: 2 },
+            Position { x: 3, y: 3 },
+            Position { x: 2, y: 3 },
+            Position { x: 1, y: 3 },
+            Position { x: 0, y: 3 },
+            Position { x: 0, y: 4 },
+            Position { x: 0, y: 5 },
+            Position { x: 0, y: 6 },
+        ]));
+
+        assert_eq!(test_data.get_trails(&trailhead, &rules, Some(1)).len(), 1);
+        assert!(test_data
+            .get_trails(&Position { x: 0, y: 6 }, &rules, None)
+            .is_empty());
+
+        // Every listed trail is counted by the rating
+        let test_data = get_test_input();
+
+        let trails_count: usize = test_data
+            .find_trailheads(&rules)
+            .iter()
+            .map(|trailhead| test_data.get_trails(trailhead, &rules, None).len())
+            .sum();
+
+        assert_eq!(trails_count, 81);
+    }
+
+    #[test]
+    fn check_tops_trailheads() {
+        let test_data = get_test_input();
+        let rules = TrailRules::default();
+
+        let tops_trailheads = test_data.get_tops_trailheads(&rules);
+
+        assert_eq!(
+            tops_trailheads
+                .values()
+                .map(|trailheads| trailheads.len())
+                .sum::<usize>(),
+            36
+        );
+        assert_eq!(
+            tops_trailheads[&Position { x: 1, y: 0 }],
+            vec![
+                Position { x: 2, y: 0 },
+                Position { x: 4, y: 0 },
+                Position { x: 4, y: 2 },
+                Position { x: 0, y: 6 },
+                Position { x: 1, y: 7 },
+            ]
+        );
+    }
+
+    #[test]
+    fn check_trail_rendering() {
+        let test_data = TopographicMap::parse("data/test_impassable.txt");
+        let rules = TrailRules::default();
+
+        let trails = test_data.get_trails(&Position { x: 3, y: 0 }, &rules, Some(1));
+
+        assert_eq!(
+            test_data.render_trail(&trails[0], TRAIL_MARKER),
+            ["...#...", "...#...", "...#...", "654####", "7.....#", "8.....#", "9.....#"]
+                .join("\n")
+        );
     }
 }
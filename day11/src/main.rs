@@ -1,52 +1,107 @@
-use std::{collections::HashMap, fs::File, io::Read};
-
-#[derive(Clone, Debug, PartialEq)]
-struct StoneRecord {
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fs::{self, File},
+    io::Read,
+    sync::Arc,
+};
+
+use num_bigint::BigUint;
+
+type StonePredicate = Arc<dyn Fn(u64) -> bool + Send + Sync>;
+// None is returned when a new id doesn't fit
+type StoneTransform = Arc<dyn Fn(u64) -> Option<Vec<u64>> + Send + Sync>;
+
+// A rule turned the stone into an id too big to be represented
+#[derive(Debug, PartialEq)]
+struct StoneIdOverflow {
     id: u64,
-    count: usize,
 }
 
-impl StoneRecord {
-    fn blink(&self) -> Vec<StoneRecord> {
-        let mut new_stones = Vec::with_capacity(2);
+#[derive(Clone)]
+struct StoneRule {
+    predicate: StonePredicate,
+    transform: StoneTransform,
+}
 
-        if self.id == 0 {
-            new_stones.push(StoneRecord {
-                id: 1,
-                count: self.count,
-            });
-            return new_stones;
+impl StoneRule {
+    fn new(
+        predicate: impl Fn(u64) -> bool + Send + Sync + 'static,
+        transform: impl Fn(u64) -> Option<Vec<u64>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            predicate: Arc::new(predicate),
+            transform: Arc::new(transform),
         }
+    }
+}
 
-        let stone_id_string = self.id.to_string();
+// Only the first rule matching a stone gets applied, stones without any match stay the same
+#[derive(Clone)]
+struct StoneRules(Vec<StoneRule>);
 
-        if stone_id_string.len() % 2 == 0 {
-            let first_part = stone_id_string[0..stone_id_string.len() / 2].to_string();
-            let second_part = stone_id_string[stone_id_string.len() / 2..].to_string();
+impl StoneRules {
+    fn new(rules: Vec<StoneRule>) -> Self {
+        Self(rules)
+    }
 
-            new_stones.push(StoneRecord {
-                id: first_part.parse().expect("Cant' convert to number"),
-                count: self.count,
-            });
-            new_stones.push(StoneRecord {
-                id: second_part.parse().expect("Cant' convert to number"),
-                count: self.count,
-            });
+    fn get_default_rules() -> Self {
+        Self::new(vec![
+            StoneRule::new(|id| id == 0, |_| Some(vec![1])),
+            StoneRule::new(
+                |id| Self::count_digits(id).is_multiple_of(2),
+                |id| {
+                    let (first_part, second_part) = Self::split_digits(id);
+                    Some(vec![first_part, second_part])
+                },
+            ),
+            StoneRule::new(|_| true, |id| id.checked_mul(2024).map(|id| vec![id])),
+        ])
+    }
 
-            return new_stones;
+    fn apply(&self, id: u64) -> Result<Vec<u64>, StoneIdOverflow> {
+        match self.0.iter().find(|rule| (rule.predicate)(id)) {
+            Some(rule) => (rule.transform)(id).ok_or(StoneIdOverflow { id }),
+            None => Ok(vec![id]),
         }
+    }
 
-        new_stones.push(StoneRecord {
-            id: self.id * 2024,
-            count: self.count,
-        });
+    fn count_digits(id: u64) -> u32 {
+        id.checked_ilog10().unwrap_or_default() + 1
+    }
+
+    // Splits the number into its left and right halves of digits
+    fn split_digits(id: u64) -> (u64, u64) {
+        let divisor = 10u64.pow(Self::count_digits(id) / 2);
 
-        new_stones
+        (id / divisor, id % divisor)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct StoneRecord {
+    id: u64,
+    count: usize,
+}
+
+impl StoneRecord {
+    fn blink(&self, rules: &StoneRules) -> Result<Vec<StoneRecord>, StoneIdOverflow> {
+        Ok(rules
+            .apply(self.id)?
+            .into_iter()
+            .map(|id| StoneRecord {
+                id,
+                count: self.count,
+            })
+            .collect())
     }
 }
 
 #[derive(Clone)]
-struct StonesList(Vec<StoneRecord>);
+struct StonesList {
+    stones: Vec<StoneRecord>,
+    rules: StoneRules,
+}
 
 impl StonesList {
     fn parse(file_path: &str) -> Self {
@@ -65,30 +120,54 @@ impl StonesList {
             })
             .collect::<Vec<StoneRecord>>();
 
-        Self(data)
+        Self::new(data)
+    }
+
+    fn new(stones: Vec<StoneRecord>) -> Self {
+        Self {
+            stones,
+            rules: StoneRules::get_default_rules(),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn with_rules(mut self, rules: StoneRules) -> Self {
+        self.rules = rules;
+        self
     }
 
-    fn blink(&mut self) {
-        self.0 = self.0.iter().flat_map(|stone| stone.blink()).collect();
+    // Stones are left as they were if any of them overflows
+    fn blink(&mut self) -> Result<(), StoneIdOverflow> {
+        let mut stones = Vec::with_capacity(self.stones.len());
+
+        for stone in self.stones.iter() {
+            stones.extend(stone.blink(&self.rules)?);
+        }
+
+        self.stones = stones;
+
+        Ok(())
     }
 
-    fn blink_n(&mut self, n_blinks: usize) {
+    fn blink_n(&mut self, n_blinks: usize) -> Result<(), StoneIdOverflow> {
         for _ in 0..n_blinks {
-            self.blink();
+            self.blink()?;
             self.deduplicate();
         }
+
+        Ok(())
     }
 
     fn deduplicate(&mut self) {
         let mut map = HashMap::new();
 
-        self.0.iter().for_each(|stone| {
+        self.stones.iter().for_each(|stone| {
             map.entry(stone.id)
                 .and_modify(|x| *x += stone.count)
                 .or_insert(stone.count);
         });
 
-        self.0 = map
+        self.stones = map
             .iter()
             .map(|(key, value)| StoneRecord {
                 id: *key,
@@ -97,21 +176,224 @@ impl StonesList {
             .collect();
     }
 
+    // Records the population after every blink, starting with the current one
+    fn blink_n_with_stats(
+        &mut self,
+        n_blinks: usize,
+        most_common_size: usize,
+    ) -> Result<StonesHistory, StoneIdOverflow> {
+        let mut seen_ids = HashSet::new();
+        let mut history = Vec::with_capacity(n_blinks + 1);
+
+        self.deduplicate();
+
+        for blink in 0..=n_blinks {
+            if blink > 0 {
+                self.blink()?;
+                self.deduplicate();
+            }
+
+            let new_ids = self
+                .stones
+                .iter()
+                .filter(|stone| seen_ids.insert(stone.id))
+                .count();
+
+            let mut most_common = self.stones.clone();
+            most_common.sort_by(|a, b| b.count.cmp(&a.count).then(a.id.cmp(&b.id)));
+            most_common.truncate(most_common_size);
+
+            let stones = self.len();
+            // An empty list can't grow any further
+            let growth_ratio = match history.last() {
+                Some(BlinkStats {
+                    stones: previous_stones,
+                    ..
+                }) if *previous_stones > 0 => stones as f64 / *previous_stones as f64,
+                _ => 1.0,
+            };
+
+            history.push(BlinkStats {
+                blink,
+                stones,
+                distinct_ids: self.stones.len(),
+                new_ids,
+                growth_ratio,
+                most_common,
+            });
+        }
+
+        Ok(StonesHistory(history))
+    }
+
     fn len(&self) -> usize {
-        self.0.iter().fold(0, |i, stone| i + stone.count)
+        self.stones.iter().fold(0, |i, stone| i + stone.count)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct BlinkStats {
+    blink: usize,
+    stones: usize,
+    distinct_ids: usize,
+    // Ids that didn't show up in any of the previous blinks
+    new_ids: usize,
+    growth_ratio: f64,
+    most_common: Vec<StoneRecord>,
+}
+
+#[derive(Debug)]
+struct StonesHistory(Vec<BlinkStats>);
+
+impl StonesHistory {
+    // First blink since which no new ids have shown up, if any blink after it was recorded
+    #[allow(dead_code)]
+    fn get_closure_blink(&self) -> Option<usize> {
+        let last_growth = self.0.iter().rev().find(|stats| stats.new_ids > 0)?;
+        let last_blink = self.0.last()?.blink;
+
+        if last_growth.blink == last_blink {
+            return None;
+        }
+
+        Some(last_growth.blink + 1)
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("blink,stones,distinct_ids,new_ids,growth_ratio,most_common\n");
+
+        for stats in self.0.iter() {
+            let most_common = stats
+                .most_common
+                .iter()
+                .map(|stone| format!("{}:{}", stone.id, stone.count))
+                .collect::<Vec<String>>()
+                .join(";");
+
+            csv.push_str(&format!(
+                "{},{},{},{},{:.4},{}\n",
+                stats.blink,
+                stats.stones,
+                stats.distinct_ids,
+                stats.new_ids,
+                stats.growth_ratio,
+                most_common
+            ));
+        }
+
+        csv
+    }
+}
+
+// Remembers how many stones every id becomes after each amount of blinks,
+// so the work is shared between every query using the same rules
+struct StonesCounter {
+    rules: StoneRules,
+    children: HashMap<u64, Vec<u64>>,
+    counts: HashMap<(u64, usize), BigUint>,
+}
+
+impl StonesCounter {
+    fn new(rules: StoneRules) -> Self {
+        Self {
+            rules,
+            children: HashMap::new(),
+            counts: HashMap::new(),
+        }
+    }
+
+    fn count_after(&mut self, id: u64, n_blinks: usize) -> Result<BigUint, StoneIdOverflow> {
+        // Only ids reachable within the remaining blinks are expanded, and every
+        // count waits on the stack until its children counts are known
+        let mut pending = vec![(id, n_blinks)];
+
+        while let Some(&(next_id, blinks)) = pending.last() {
+            if self.counts.contains_key(&(next_id, blinks)) {
+                pending.pop();
+                continue;
+            }
+
+            if blinks == 0 {
+                self.counts.insert((next_id, 0), BigUint::from(1u8));
+                pending.pop();
+                continue;
+            }
+
+            if !self.children.contains_key(&next_id) {
+                let children = self.rules.apply(next_id)?;
+                self.children.insert(next_id, children);
+            }
+
+            let children = &self.children[&next_id];
+
+            let missing_children: Vec<(u64, usize)> = children
+                .iter()
+                .map(|&child| (child, blinks - 1))
+                .filter(|child| !self.counts.contains_key(child))
+                .collect();
+
+            if !missing_children.is_empty() {
+                pending.extend(missing_children);
+                continue;
+            }
+
+            let count = children
+                .iter()
+                .map(|&child| &self.counts[&(child, blinks - 1)])
+                .sum();
+
+            self.counts.insert((next_id, blinks), count);
+            pending.pop();
+        }
+
+        Ok(self.counts[&(id, n_blinks)].clone())
+    }
+
+    fn count_list_after(
+        &mut self,
+        stones_list: &StonesList,
+        n_blinks: usize,
+    ) -> Result<BigUint, StoneIdOverflow> {
+        stones_list
+            .stones
+            .iter()
+            .map(|stone| Ok(self.count_after(stone.id, n_blinks)? * stone.count))
+            .sum()
     }
 }
 
 fn main() {
     let mut stones_list = StonesList::parse("day11/data/input.txt");
 
-    stones_list.blink_n(25);
+    stones_list
+        .blink_n(25)
+        .expect("Stone ids can't get that big");
 
     println!("Part 1 result: {}", stones_list.len());
 
-    stones_list.blink_n(75 - 25);
+    let mut counter = StonesCounter::new(StoneRules::get_default_rules());
 
-    println!("Part 2 result: {}", stones_list.len());
+    println!(
+        "Part 2 result: {}",
+        counter
+            .count_list_after(&stones_list, 75 - 25)
+            .expect("Stone ids can't get that big")
+    );
+
+    // Statistics for every blink are exported when a CSV path is given with --csv
+    let args: Vec<String> = env::args().collect();
+
+    if let Some(csv_path) = args
+        .iter()
+        .position(|arg| arg == "--csv")
+        .and_then(|i| args.get(i + 1))
+    {
+        let history = StonesList::parse("day11/data/input.txt")
+            .blink_n_with_stats(75, 5)
+            .expect("Stone ids can't get that big");
+
+        fs::write(csv_path, history.to_csv()).expect("File can't be written");
+    }
 }
 
 #[cfg(test)]
@@ -120,7 +402,7 @@ mod tests {
     use super::*;
 
     fn get_test_input() -> StonesList {
-        StonesList(vec![
+        StonesList::new(vec![
             StoneRecord { id: 125, count: 1 },
             StoneRecord { id: 17, count: 1 },
         ])
@@ -132,19 +414,214 @@ mod tests {
 
         let test_data = get_test_input();
 
-        assert_eq!(input.0, test_data.0);
+        assert_eq!(input.stones, test_data.stones);
     }
 
     #[test]
     fn check_blink_n_size() {
         let mut test_data = get_test_input();
 
-        test_data.blink_n(6);
+        test_data.blink_n(6).unwrap();
 
         assert_eq!(test_data.len(), 22);
 
-        test_data.blink_n(25 - 6);
+        test_data.blink_n(25 - 6).unwrap();
 
         assert_eq!(test_data.len(), 55312);
     }
+
+    #[test]
+    fn check_digits_split() {
+        assert_eq!(StoneRules::count_digits(0), 1);
+        assert_eq!(StoneRules::count_digits(1000), 4);
+        assert_eq!(StoneRules::split_digits(253000), (253, 0));
+        assert_eq!(StoneRules::split_digits(1000), (10, 0));
+        assert_eq!(StoneRules::split_digits(17), (1, 7));
+    }
+
+    #[test]
+    fn check_custom_rules() {
+        let rules = StoneRules::get_default_rules();
+
+        assert_eq!(rules.apply(0), Ok(vec![1]));
+        assert_eq!(rules.apply(1000), Ok(vec![10, 0]));
+        assert_eq!(rules.apply(125), Ok(vec![253000]));
+
+        // Odd stones split in three while the rest stay the same,
+        // so only the original stones keep adding two neighbours every blink
+        let rules = StoneRules::new(vec![StoneRule::new(
+            |id| id % 2 == 1,
+            |id| Some(vec![id - 1, id, id + 1]),
+        )]);
+
+        let mut test_data = get_test_input().with_rules(rules);
+
+        test_data.blink_n(3).unwrap();
+
+        assert_eq!(test_data.len(), 2 + 3 * 2 * 2);
+    }
+
+    #[test]
+    fn check_count_after() {
+        let test_data = get_test_input();
+        let mut counter = StonesCounter::new(StoneRules::get_default_rules());
+
+        assert_eq!(counter.count_after(125, 6).unwrap(), BigUint::from(7u8));
+        assert_eq!(
+            counter.count_list_after(&test_data, 6).unwrap(),
+            BigUint::from(22u8)
+        );
+        assert_eq!(
+            counter.count_list_after(&test_data, 25).unwrap(),
+            BigUint::from(55312u32)
+        );
+
+        for id in [0, 1, 9, 2024, 253000] {
+            let mut stones_list = StonesList::new(vec![StoneRecord { id, count: 1 }]);
+            stones_list.blink_n(30).unwrap();
+
+            assert_eq!(
+                counter.count_after(id, 30).unwrap(),
+                BigUint::from(stones_list.len())
+            );
+        }
+    }
+
+    #[test]
+    fn check_long_count_after() {
+        let mut counter = StonesCounter::new(StoneRules::get_default_rules());
+
+        let count = counter.count_after(0, 2000).unwrap();
+
+        assert!(count.bits() > 1000);
+
+        // A zero always turns into a one, which was already cached by the previous query
+        assert!(counter.counts.contains_key(&(1, 1999)));
+        assert_eq!(counter.count_after(1, 1999).unwrap(), count);
+    }
+
+    #[test]
+    fn check_bounded_count_after() {
+        let mut counter = StonesCounter::new(StoneRules::get_default_rules());
+
+        // Stones that don't blink are never split nor multiplied
+        assert_eq!(
+            counter.count_after(1_000_000_000_000_000_000, 0).unwrap(),
+            BigUint::from(1u8)
+        );
+        assert!(counter.children.is_empty());
+
+        // Rules creating new ids forever only get expanded as far as the blinks go
+        let rules = StoneRules::new(vec![StoneRule::new(|_| true, |id| Some(vec![id + 1]))]);
+        let mut counter = StonesCounter::new(rules);
+
+        assert_eq!(counter.count_after(0, 1).unwrap(), BigUint::from(1u8));
+        assert_eq!(counter.count_after(0, 1000).unwrap(), BigUint::from(1u8));
+        assert_eq!(counter.children.len(), 1000);
+    }
+
+    #[test]
+    fn check_large_ids() {
+        let mut counter = StonesCounter::new(StoneRules::get_default_rules());
+
+        assert_eq!(
+            counter.count_after(999_999_999_999_999, 1),
+            Ok(BigUint::from(1u8))
+        );
+        assert_eq!(counter.count_after(u64::MAX, 2), Ok(BigUint::from(4u8)));
+
+        // Odd sized ids this big can't be multiplied without overflowing
+        assert_eq!(
+            counter.count_after(1_000_000_000_000_000_000, 1),
+            Err(StoneIdOverflow {
+                id: 1_000_000_000_000_000_000
+            })
+        );
+
+        let mut test_data = StonesList::new(vec![
+            StoneRecord { id: 0, count: 1 },
+            StoneRecord {
+                id: 1_000_000_000_000_000_000,
+                count: 1,
+            },
+        ]);
+
+        assert_eq!(
+            test_data.blink_n(2),
+            Err(StoneIdOverflow {
+                id: 1_000_000_000_000_000_000
+            })
+        );
+        assert_eq!(test_data.len(), 2);
+    }
+
+    #[test]
+    fn check_blink_stats() {
+        let mut test_data = get_test_input();
+
+        let history = test_data.blink_n_with_stats(6, 2).unwrap();
+
+        assert_eq!(history.0.len(), 7);
+        assert_eq!(history.0[6].stones, 22);
+        assert_eq!(
+            history.0[4],
+            BlinkStats {
+                blink: 4,
+                stones: 9,
+                distinct_ids: 8,
+                // Both 0 and 2024 already showed up two blinks before
+                new_ids: 6,
+                growth_ratio: 1.8,
+                most_common: vec![
+                    StoneRecord { id: 2, count: 2 },
+                    StoneRecord { id: 0, count: 1 },
+                ],
+            }
+        );
+
+        let csv = history.to_csv();
+        let csv_lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            csv_lines[0],
+            "blink,stones,distinct_ids,new_ids,growth_ratio,most_common"
+        );
+        assert_eq!(csv_lines[1], "0,2,2,2,1.0000,17:1;125:1");
+        assert_eq!(csv_lines[2], "1,3,3,3,1.5000,1:1;7:1");
+
+        // Nothing grows out of an empty list
+        let history = StonesList::new(Vec::new())
+            .blink_n_with_stats(2, 1)
+            .unwrap();
+
+        assert!(history.0.iter().all(|stats| stats.growth_ratio == 1.0));
+    }
+
+    #[test]
+    fn check_ids_closure() {
+        let mut test_data = StonesList::new(vec![StoneRecord { id: 0, count: 1 }]);
+
+        let history = test_data.blink_n_with_stats(100, 1).unwrap();
+        let closure_blink = history
+            .get_closure_blink()
+            .expect("Ids should stop growing");
+
+        assert!(history.0[closure_blink..]
+            .iter()
+            .all(|stats| stats.new_ids == 0));
+        assert!(history.0[closure_blink - 1].new_ids > 0);
+
+        // Every id ever seen is one of the ids expanded from the first stone
+        let mut counter = StonesCounter::new(StoneRules::get_default_rules());
+        counter.count_after(0, 100).unwrap();
+
+        assert_eq!(
+            history.0.iter().map(|stats| stats.new_ids).sum::<usize>(),
+            counter.children.len()
+        );
+
+        let history = test_data.blink_n_with_stats(0, 1).unwrap();
+
+        assert_eq!(history.get_closure_blink(), None);
+    }
 }
@@ -1,111 +1,96 @@
 use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
+    env,
+    fmt::Display,
+    fs::{self, File},
     io::{BufRead, BufReader},
 };
 
-use common::{Direction, Position};
+use common::{Direction, Grid, Position, Region};
 
-struct Region {
-    #[allow(dead_code)]
-    id: char,
-    items: HashSet<Position>,
-}
-
-impl Region {
-    fn get_area(&self) -> usize {
-        self.items.len()
-    }
-
-    fn get_perimeter(&self) -> usize {
-        let mut perimeter = 0;
-
-        let directions = Direction::generate_basic_directions_list();
+// Size in pixels of every garden plot within the fence plans
+const SVG_PLOT_SIZE: isize = 10;
 
-        for item in self.items.iter() {
-            for direction in directions.iter() {
-                let new_pos = Direction::apply_offset(direction, item.x, item.y);
+// Prices of the fences around a region
+trait FencePrice {
+    fn calculate_price(&self) -> u64;
 
-                if !self.items.contains(&new_pos) {
-                    perimeter += 1;
-                }
-            }
-        }
+    fn calculate_bulk_price(&self) -> u64;
+}
 
-        perimeter
+impl FencePrice for Region<char> {
+    fn calculate_price(&self) -> u64 {
+        self.get_area() as u64 * self.get_perimeter() as u64
     }
 
-    fn get_sides(&self) -> usize {
-        let mut sides = 0;
-
-        let directions = Direction::generate_basic_directions_list();
-        let mut items_wall: [HashMap<isize, Vec<isize>>; 4] = [
-            HashMap::new(),
-            HashMap::new(),
-            HashMap::new(),
-            HashMap::new(),
-        ];
-
-        // Classify walls
-        for item in self.items.iter() {
-            for direction in directions.iter() {
-                let new_pos = Direction::apply_offset(direction, item.x, item.y);
-
-                if self.items.contains(&new_pos) {
-                    continue;
-                }
-
-                match direction {
-                    Direction::Top | Direction::Bottom => items_wall[direction.clone() as usize]
-                        .entry(new_pos.y)
-                        .and_modify(|items| items.push(new_pos.x))
-                        .or_insert(vec![new_pos.x]),
-                    Direction::Right | Direction::Left => items_wall[direction.clone() as usize]
-                        .entry(new_pos.x)
-                        .and_modify(|items| items.push(new_pos.y))
-                        .or_insert(vec![new_pos.y]),
-                    Direction::TopLeft => unreachable!(),
-                    Direction::TopRight => unreachable!(),
-                    Direction::BottomRight => unreachable!(),
-                    Direction::BottomLeft => unreachable!(),
-                };
-            }
-        }
-
-        // Deduplicate walls
-        for items_wall in items_wall.iter_mut() {
-            for (_, items) in items_wall.iter_mut() {
-                // A line of walls is split by gaps between them, thus items must be sorted first
-                items.sort();
-
-                let mut sides_counter = 1;
+    fn calculate_bulk_price(&self) -> u64 {
+        self.get_area() as u64 * self.get_sides() as u64
+    }
+}
 
-                for pair in items.windows(2) {
-                    if pair[0].abs_diff(pair[1]) > 1 {
-                        sides_counter += 1;
-                    }
-                }
+#[derive(Debug, PartialEq)]
+struct RegionReport {
+    id: char,
+    area: usize,
+    perimeter: usize,
+    sides: usize,
+    bounds: (Position, Position),
+    price: u64,
+    bulk_price: u64,
+}
 
-                sides += sides_counter;
-            }
+impl From<&Region<char>> for RegionReport {
+    fn from(region: &Region<char>) -> Self {
+        Self {
+            id: region.id,
+            area: region.get_area(),
+            perimeter: region.get_perimeter(),
+            sides: region.get_sides(),
+            bounds: region.get_bounds().expect("Regions can't be empty"),
+            price: region.calculate_price(),
+            bulk_price: region.calculate_bulk_price(),
         }
-
-        sides
-    }
-
-    fn calculate_price(&self) -> u64 {
-        self.get_area() as u64 * self.get_perimeter() as u64
     }
+}
 
-    fn calculate_bulk_price(&self) -> u64 {
-        self.get_area() as u64 * self.get_sides() as u64
+impl Display for RegionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (min, max) = &self.bounds;
+
+        write!(
+            f,
+            "{}: area {}, perimeter {}, sides {}, bounds ({}, {})-({}, {}), price {}, bulk price {}",
+            self.id,
+            self.area,
+            self.perimeter,
+            self.sides,
+            min.x,
+            min.y,
+            max.x,
+            max.y,
+            self.price,
+            self.bulk_price
+        )
     }
 }
 
-struct Garden(Vec<Region>);
+struct Garden(Vec<Region<char>>);
 
 impl Garden {
     fn parse(file_path: &str) -> Self {
+        Self::parse_with(
+            file_path,
+            &Direction::generate_basic_directions_list(),
+            |a, b| a == b,
+        )
+    }
+
+    // Plots of the same region are reached through the given directions,
+    // and can hold any plant considered the same species
+    fn parse_with(
+        file_path: &str,
+        directions: &[Direction],
+        is_same_plant: impl Fn(&char, &char) -> bool,
+    ) -> Self {
         let file = File::open(file_path).expect("File can't be read");
         let reader = BufReader::new(file);
 
@@ -119,77 +104,60 @@ impl Garden {
             garden_data.push(line_data);
         }
 
-        let regions = Self::get_regions(&garden_data);
+        let regions = Grid::new(garden_data).get_regions_by(directions, is_same_plant);
 
         Self(regions)
     }
 
-    fn get_regions(data: &[Vec<char>]) -> Vec<Region> {
-        let mut regions = Vec::new();
-        let mut visited_pos = HashSet::new();
-
-        for (y, row) in data.iter().enumerate() {
-            for (x, _) in row.iter().enumerate() {
-                let position = Position {
-                    x: x as isize,
-                    y: y as isize,
-                };
-
-                if visited_pos.contains(&position) {
-                    continue;
-                }
-
-                let region = Self::get_region(data, position);
-
-                visited_pos.extend(region.items.clone());
-
-                regions.push(region);
-            }
-        }
-
-        regions
+    fn get_regions_report(&self) -> Vec<RegionReport> {
+        self.0.iter().map(RegionReport::from).collect()
     }
 
-    fn get_region(data: &[Vec<char>], initial_pos: Position) -> Region {
-        let region_id = data[initial_pos.y as usize][initial_pos.x as usize];
-        let mut region_items = HashSet::new();
-
-        let directions = Direction::generate_basic_directions_list();
-
-        let mut next_positions = vec![initial_pos];
-
-        while let Some(next_pos) = next_positions.pop() {
-            if !Self::is_within_bounds(data, &next_pos) {
-                continue;
+    // Fence plan with every straight fence as a line, grouped by region
+    fn get_fences_svg(&self) -> String {
+        let width = self
+            .0
+            .iter()
+            .filter_map(|region| region.get_bounds())
+            .map(|(_, max)| max.x + 1)
+            .max()
+            .unwrap_or_default();
+        let height = self
+            .0
+            .iter()
+            .filter_map(|region| region.get_bounds())
+            .map(|(_, max)| max.y + 1)
+            .max()
+            .unwrap_or_default();
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+            width * SVG_PLOT_SIZE,
+            height * SVG_PLOT_SIZE
+        );
+
+        for region in self.0.iter() {
+            svg.push_str(&format!(
+                "  <g data-plant=\"{}\" stroke=\"black\">\n",
+                region.id
+            ));
+
+            for (start, end) in region.get_fence_segments() {
+                svg.push_str(&format!(
+                    "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" />\n",
+                    start.x * SVG_PLOT_SIZE,
+                    start.y * SVG_PLOT_SIZE,
+                    end.x * SVG_PLOT_SIZE,
+                    end.y * SVG_PLOT_SIZE
+                ));
             }
 
-            let item = data[next_pos.y as usize][next_pos.x as usize];
-
-            if item != region_id {
-                continue;
-            }
-
-            region_items.insert(next_pos.clone());
-
-            for direction in directions.iter() {
-                let new_pos = Direction::apply_offset(direction, next_pos.x, next_pos.y);
-
-                if region_items.contains(&new_pos) {
-                    continue;
-                }
-
-                next_positions.push(new_pos);
-            }
+            svg.push_str("  </g>\n");
         }
 
-        Region {
-            id: region_id,
-            items: region_items,
-        }
-    }
+        svg.push_str("</svg>\n");
 
-    fn is_within_bounds(data: &[Vec<char>], pos: &Position) -> bool {
-        pos.x >= 0 && pos.x < data[0].len() as isize && pos.y >= 0 && pos.y < data.len() as isize
+        svg
     }
 
     fn calculate_total_price(&self) -> u64 {
@@ -207,6 +175,28 @@ fn main() {
     println!("Part 1 result: {}", garden.calculate_total_price());
 
     println!("Part 2 result: {}", garden.calculate_total_bulk_price());
+
+    // Every region report, one per line, and the fence plan are exported when their paths are given
+    let args: Vec<String> = env::args().collect();
+    let get_path = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1))
+    };
+
+    if let Some(report_path) = get_path("--report") {
+        let report: String = garden
+            .get_regions_report()
+            .iter()
+            .map(|region_report| format!("{}\n", region_report))
+            .collect();
+
+        fs::write(report_path, report).expect("File can't be written");
+    }
+
+    if let Some(svg_path) = get_path("--svg") {
+        fs::write(svg_path, garden.get_fences_svg()).expect("File can't be written");
+    }
 }
 
 #[cfg(test)]
@@ -309,4 +299,155 @@ mod tests {
 
         assert_eq!(input.calculate_total_bulk_price(), 368);
     }
+
+    #[test]
+    fn check_region_holes_test2() {
+        let input = Garden::parse("data/test2.txt");
+
+        let region = input.0.iter().find(|region| region.id == 'O').unwrap();
+
+        assert_eq!(region.get_holes().len(), 4);
+        assert_eq!(region.get_outer_perimeter(), 20);
+        assert_eq!(region.get_inner_perimeter(), 16);
+        assert_eq!(region.get_outer_sides(), 4);
+        assert_eq!(region.get_inner_sides(), 16);
+
+        for other_region in input.0.iter().filter(|region| region.id == 'X') {
+            assert!(region.encloses(other_region));
+            assert!(!other_region.encloses(region));
+            assert!(other_region.get_holes().is_empty());
+        }
+    }
+
+    #[test]
+    fn check_region_holes_test5() {
+        let input = Garden::parse("data/test5.txt");
+
+        let region = input.0.iter().find(|region| region.id == 'A').unwrap();
+
+        assert_eq!(region.get_holes().len(), 2);
+        assert_eq!(region.get_sides(), 12);
+        assert_eq!(region.get_outer_sides(), 4);
+        assert_eq!(region.get_inner_sides(), 8);
+        assert_eq!(region.get_outer_perimeter(), 24);
+        assert_eq!(region.get_inner_perimeter(), 16);
+    }
+
+    #[test]
+    fn check_regions_report_test1() {
+        let input = Garden::parse("data/test1.txt");
+
+        let report = input.get_regions_report();
+
+        assert_eq!(report.len(), 5);
+        assert_eq!(
+            report[0],
+            RegionReport {
+                id: 'A',
+                area: 4,
+                perimeter: 10,
+                sides: 4,
+                bounds: (Position { x: 0, y: 0 }, Position { x: 3, y: 0 }),
+                price: 40,
+                bulk_price: 16,
+            }
+        );
+        assert_eq!(
+            report[0].to_string(),
+            "A: area 4, perimeter 10, sides 4, bounds (0, 0)-(3, 0), price 40, bulk price 16"
+        );
+        assert_eq!(
+            report.iter().map(|region| region.price).sum::<u64>(),
+            input.calculate_total_price()
+        );
+    }
+
+    #[test]
+    fn check_fence_segments() {
+        for file_path in ["data/test1.txt", "data/test2.txt", "data/test5.txt"] {
+            let input = Garden::parse(file_path);
+
+            for region in input.0.iter() {
+                let segments = region.get_fence_segments();
+
+                assert_eq!(segments.len(), region.get_sides());
+                assert_eq!(
+                    segments
+                        .iter()
+                        .map(|(start, end)| start.x.abs_diff(end.x) + start.y.abs_diff(end.y))
+                        .sum::<usize>(),
+                    region.get_perimeter()
+                );
+            }
+        }
+
+        let input = Garden::parse("data/test1.txt");
+
+        let svg = input.get_fences_svg();
+
+        assert!(svg
+            .starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"40\">"));
+        assert!(svg.contains("<line x1=\"0\" y1=\"0\" x2=\"40\" y2=\"0\" />"));
+        assert_eq!(svg.matches("<line").count(), 4 + 4 + 8 + 4 + 4);
+    }
+
+    #[test]
+    fn check_diagonal_regions() {
+        let directions = Direction::generate_directions_list();
+
+        let input = Garden::parse_with("data/test5.txt", &directions, |a, b| a == b);
+
+        // Both B regions touch each other diagonally
+        assert_eq!(input.0.len(), 2);
+        assert_eq!(
+            input
+                .0
+                .iter()
+                .find(|region| region.id == 'B')
+                .unwrap()
+                .get_area(),
+            8
+        );
+
+        let input = Garden::parse_with("data/test2.txt", &directions, |a, b| a == b);
+
+        assert_eq!(input.0.len(), 5);
+
+        // Regions only grow sideways, so every row is split on its own
+        for direction in [Direction::Right, Direction::Left] {
+            let input = Garden::parse_with("data/test1.txt", &[direction], |a, b| a == b);
+
+            assert_eq!(input.0.len(), 8);
+            assert_eq!(
+                input
+                    .0
+                    .iter()
+                    .map(|region| region.get_area())
+                    .sum::<usize>(),
+                16
+            );
+        }
+    }
+
+    #[test]
+    fn check_equivalent_plants_regions() {
+        let grid = Grid::new(vec![
+            "AAaa".chars().collect(),
+            "BbCC".chars().collect(),
+            "bBcd".chars().collect(),
+        ]);
+        let directions = Direction::generate_basic_directions_list();
+
+        assert_eq!(grid.get_regions().len(), 9);
+
+        let regions = grid.get_regions_by(&directions, |a, b| a.eq_ignore_ascii_case(b));
+
+        assert_eq!(
+            regions
+                .iter()
+                .map(|region| (region.id, region.get_area()))
+                .collect::<Vec<(char, usize)>>(),
+            vec![('A', 4), ('B', 4), ('C', 3), ('d', 1)]
+        );
+    }
 }
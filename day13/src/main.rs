@@ -5,6 +5,9 @@ use std::{
 
 use common::{Offset, Position};
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 #[derive(Debug, Clone, PartialEq)]
 struct ClawMachine {
     button_a_cost: usize,
@@ -16,29 +19,174 @@ struct ClawMachine {
 
 impl ClawMachine {
     fn get_prize(&self, prize_offset: Option<usize>) -> Option<usize> {
-        let prize_pos = Position {
-            x: self.prize_pos.x + prize_offset.unwrap_or_default() as isize,
-            y: self.prize_pos.y + prize_offset.unwrap_or_default() as isize,
-        };
+        self.solve(prize_offset).map(|(_, _, cost)| cost)
+    }
+
+    // Returns how many times each button is pressed to win the prize, and its cost
+    fn solve(&self, prize_offset: Option<usize>) -> Option<(usize, usize, usize)> {
+        let prize_offset = prize_offset.unwrap_or_default() as i128;
+        let prize_x = self.prize_pos.x as i128 + prize_offset;
+        let prize_y = self.prize_pos.y as i128 + prize_offset;
+
+        let (a_x, a_y) = (
+            self.button_a_offset.x as i128,
+            self.button_a_offset.y as i128,
+        );
+        let (b_x, b_y) = (
+            self.button_b_offset.x as i128,
+            self.button_b_offset.y as i128,
+        );
 
         // The claw machine is actually a two system linear equation,
-        // one for the x axis and another one for the y axis
-        // We need to find their intersection point if any, by using
-        // the already developed equation below
-        let b = ((prize_pos.y * self.button_a_offset.x) - (self.button_a_offset.y * prize_pos.x))
-            as f64
-            / ((-self.button_a_offset.y * self.button_b_offset.x)
-                + (self.button_a_offset.x * self.button_b_offset.y)) as f64;
-
-        let a = (prize_pos.x - self.button_b_offset.x * b as isize) as f64
-            / self.button_a_offset.x as f64;
-
-        // Accept only whole numbers
-        if b.trunc() != b || a.trunc() != a {
+        // one for the x axis and another one for the y axis,
+        // whose intersection point is found by using Cramer's rule
+        let determinant = a_x * b_y - a_y * b_x;
+
+        if determinant == 0 {
+            return self.solve_collinear(prize_x, prize_y);
+        }
+
+        let a_numerator = prize_x * b_y - prize_y * b_x;
+        let b_numerator = a_x * prize_y - a_y * prize_x;
+
+        // Buttons can only be pressed a whole number of times
+        if a_numerator % determinant != 0 || b_numerator % determinant != 0 {
+            return None;
+        }
+
+        let a = a_numerator / determinant;
+        let b = b_numerator / determinant;
+
+        // Buttons can't be unpressed either
+        if a < 0 || b < 0 {
+            return None;
+        }
+
+        let cost = a * self.button_a_cost as i128 + b * self.button_b_cost as i128;
+
+        Some((
+            usize::try_from(a).ok()?,
+            usize::try_from(b).ok()?,
+            usize::try_from(cost).ok()?,
+        ))
+    }
+
+    // With parallel buttons, both axes become the same equation whenever the prize
+    // lies on their line, leaving many ways of winning from which the cheapest is picked
+    fn solve_collinear(&self, prize_x: i128, prize_y: i128) -> Option<(usize, usize, usize)> {
+        let (a_x, a_y) = (
+            self.button_a_offset.x as i128,
+            self.button_a_offset.y as i128,
+        );
+        let (b_x, b_y) = (
+            self.button_b_offset.x as i128,
+            self.button_b_offset.y as i128,
+        );
+
+        let (line_x, line_y) = match (a_x, a_y) {
+            (0, 0) => (b_x, b_y),
+            _ => (a_x, a_y),
+        };
+
+        // Neither button moves the claw at all
+        if line_x == 0 && line_y == 0 {
+            return match (prize_x, prize_y) {
+                (0, 0) => Some((0, 0, 0)),
+                _ => None,
+            };
+        }
+
+        if line_x * prize_y - line_y * prize_x != 0 {
             return None;
         }
 
-        Some(self.button_a_cost * a as usize + self.button_b_cost * b as usize)
+        // Any axis the buttons move along is enough, as the other one is proportional to it
+        let (a_step, b_step, prize) = match line_x {
+            0 => (a_y, b_y, prize_y),
+            _ => (a_x, b_x, prize_x),
+        };
+
+        let (gcd, a_factor, b_factor) = Self::extended_gcd(a_step, b_step);
+
+        if prize % gcd != 0 {
+            return None;
+        }
+
+        // Every solution is a = a_0 + k * a_shift and b = b_0 - k * b_shift
+        let a_0 = a_factor * (prize / gcd);
+        let b_0 = b_factor * (prize / gcd);
+        let a_shift = b_step / gcd;
+        let b_shift = a_step / gcd;
+
+        let mut lower_k: Option<i128> = None;
+        let mut upper_k: Option<i128> = None;
+
+        // Presses for both buttons can't be negative
+        for (shift, min_shift) in [(a_shift, -a_0), (-b_shift, -b_0)] {
+            match shift.signum() {
+                1 => {
+                    let bound = Self::ceil_div(min_shift, shift);
+                    lower_k = Some(lower_k.map_or(bound, |k| k.max(bound)));
+                }
+                -1 => {
+                    let bound = Self::floor_div(min_shift, shift);
+                    upper_k = Some(upper_k.map_or(bound, |k| k.min(bound)));
+                }
+                _ if min_shift > 0 => return None,
+                _ => (),
+            }
+        }
+
+        if let (Some(lower_k), Some(upper_k)) = (lower_k, upper_k) {
+            if lower_k > upper_k {
+                return None;
+            }
+        }
+
+        // The cost changes linearly with k, so the cheapest solution is on one of the bounds
+        let cost_shift =
+            a_shift * self.button_a_cost as i128 - b_shift * self.button_b_cost as i128;
+
+        let k = match cost_shift.signum() {
+            1 => lower_k?,
+            -1 => upper_k?,
+            _ => lower_k.or(upper_k)?,
+        };
+
+        let a = a_0 + k * a_shift;
+        let b = b_0 - k * b_shift;
+        let cost = a * self.button_a_cost as i128 + b * self.button_b_cost as i128;
+
+        Some((
+            usize::try_from(a).ok()?,
+            usize::try_from(b).ok()?,
+            usize::try_from(cost).ok()?,
+        ))
+    }
+
+    // Returns the positive gcd along with the factors that give it from a and b
+    fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+        if b == 0 {
+            return (a.abs(), a.signum(), 0);
+        }
+
+        let (gcd, x, y) = Self::extended_gcd(b, a % b);
+
+        (gcd, y, x - (a / b) * y)
+    }
+
+    fn floor_div(numerator: i128, denominator: i128) -> i128 {
+        let quotient = numerator / denominator;
+
+        if numerator % denominator != 0 && (numerator < 0) != (denominator < 0) {
+            quotient - 1
+        } else {
+            quotient
+        }
+    }
+
+    fn ceil_div(numerator: i128, denominator: i128) -> i128 {
+        -Self::floor_div(-numerator, denominator)
     }
 }
 
@@ -115,10 +263,12 @@ impl Arcade {
     }
 
     fn get_total_cost(&self, prize_offset: Option<usize>) -> usize {
-        self.games
-            .iter()
-            .flat_map(|game| game.get_prize(prize_offset))
-            .sum()
+        #[cfg(feature = "parallel")]
+        let games = self.games.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let games = self.games.iter();
+
+        games.filter_map(|game| game.get_prize(prize_offset)).sum()
     }
 }
 
@@ -221,4 +371,107 @@ mod tests {
 
         assert_eq!(input.get_total_cost(Some(10000000000000)), 875318608908);
     }
+
+    #[test]
+    fn check_claw_machines_presses() {
+        let input = get_test_input();
+
+        assert_eq!(input.games[0].solve(None), Some((80, 40, 280)));
+        assert_eq!(input.games[2].solve(None), Some((38, 86, 200)));
+
+        for game in input.games.iter() {
+            let Some((a, b, _)) = game.solve(Some(10000000000000)) else {
+                continue;
+            };
+
+            assert_eq!(
+                a as isize * game.button_a_offset.x + b as isize * game.button_b_offset.x,
+                game.prize_pos.x + 10000000000000
+            );
+            assert_eq!(
+                a as isize * game.button_a_offset.y + b as isize * game.button_b_offset.y,
+                game.prize_pos.y + 10000000000000
+            );
+        }
+    }
+
+    #[test]
+    fn check_negative_presses() {
+        // Reaching the prize would need to press the button A minus one times
+        let game = ClawMachine {
+            button_a_cost: 3,
+            button_a_offset: Offset { x: 2, y: 1 },
+            button_b_cost: 1,
+            button_b_offset: Offset { x: 1, y: 2 },
+            prize_pos: Position { x: 1, y: 5 },
+        };
+
+        assert_eq!(game.solve(None), None);
+    }
+
+    #[test]
+    fn check_collinear_presses() {
+        let mut game = ClawMachine {
+            button_a_cost: 3,
+            button_a_offset: Offset { x: 1, y: 1 },
+            button_b_cost: 1,
+            button_b_offset: Offset { x: 2, y: 2 },
+            prize_pos: Position { x: 10, y: 10 },
+        };
+
+        assert_eq!(game.solve(None), Some((0, 5, 5)));
+
+        // The cheapest button isn't long enough to reach the prize on its own
+        game.button_a_offset = Offset { x: 3, y: 3 };
+        game.button_b_offset = Offset { x: 2, y: 2 };
+        game.prize_pos = Position { x: 13, y: 13 };
+
+        assert_eq!(game.solve(None), Some((1, 5, 8)));
+
+        game.button_a_cost = 1;
+        game.button_b_cost = 3;
+
+        assert_eq!(game.solve(None), Some((3, 2, 9)));
+
+        // Out of the buttons line
+        game.prize_pos = Position { x: 13, y: 14 };
+
+        assert_eq!(game.solve(None), None);
+
+        // Unreachable by whole presses
+        game.button_a_offset = Offset { x: 2, y: 2 };
+        game.button_b_offset = Offset { x: 4, y: 4 };
+        game.prize_pos = Position { x: 5, y: 5 };
+
+        assert_eq!(game.solve(None), None);
+
+        // Only one of the buttons moves the claw
+        game.button_a_offset = Offset { x: 0, y: 0 };
+        game.button_b_offset = Offset { x: 0, y: 3 };
+        game.prize_pos = Position { x: 0, y: 6 };
+
+        assert_eq!(game.solve(None), Some((0, 2, 6)));
+
+        game.button_b_offset = Offset { x: 0, y: 0 };
+
+        assert_eq!(game.solve(None), None);
+
+        game.prize_pos = Position { x: 0, y: 0 };
+
+        assert_eq!(game.solve(None), Some((0, 0, 0)));
+    }
+
+    #[test]
+    fn check_collinear_opposite_presses() {
+        // Both buttons undo each other, so the cheapest presses are as few as possible
+        let game = ClawMachine {
+            button_a_cost: 3,
+            button_a_offset: Offset { x: 3, y: 6 },
+            button_b_cost: 1,
+            button_b_offset: Offset { x: -2, y: -4 },
+            prize_pos: Position { x: 5, y: 10 },
+        };
+
+        assert_eq!(game.solve(None), Some((3, 2, 11)));
+    }
 }
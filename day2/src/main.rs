@@ -3,6 +3,9 @@ use std::{
     io::{BufRead, BufReader},
 };
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 struct Reports {
     data: Vec<Report>,
 }
@@ -33,16 +36,24 @@ impl Reports {
     }
 
     fn count_safe_reports(&self) -> usize {
-        self.data
-            .iter()
+        #[cfg(feature = "parallel")]
+        let reports = self.data.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let reports = self.data.iter();
+
+        reports
             .map(|report| report.is_safe())
             .filter(|&is_safe| is_safe)
             .count()
     }
 
     fn count_safe_tolerate_reports(&self) -> usize {
-        self.data
-            .iter()
+        #[cfg(feature = "parallel")]
+        let reports = self.data.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let reports = self.data.iter();
+
+        reports
             .map(|report| report.is_safe_tolerate())
             .filter(|&is_safe| is_safe)
             .count()
@@ -174,6 +174,121 @@ impl UpdatesChecker {
         }
     }
 
+    #[allow(dead_code)]
+    fn get_valid_orderings(&self, update: &Update, limit: usize) -> Vec<Update> {
+        let mut orderings = Vec::new();
+
+        self.visit_valid_orderings(update, limit, &mut |ordering| {
+            orderings.push(Update {
+                data: ordering.to_vec(),
+            })
+        });
+
+        orderings
+    }
+
+    fn count_valid_orderings(&self, update: &Update, limit: usize) -> usize {
+        self.visit_valid_orderings(update, limit, &mut |_| {})
+    }
+
+    fn has_unique_ordering(&self, update: &Update) -> bool {
+        // Stop as soon as a second ordering is found, there is no need to keep counting
+        self.count_valid_orderings(update, 2) == 1
+    }
+
+    fn get_ambiguous_updates(&self, updates: &[Update]) -> Vec<Update> {
+        updates
+            .iter()
+            .filter(|update| !self.has_unique_ordering(update))
+            .cloned()
+            .collect()
+    }
+
+    fn visit_valid_orderings(
+        &self,
+        update: &Update,
+        limit: usize,
+        on_ordering: &mut dyn FnMut(&[Page]),
+    ) -> usize {
+        // Cyclic rules allow no ordering, but the search would only find out
+        // after going through every possible prefix
+        if self.has_cyclic_rules(update) {
+            return 0;
+        }
+
+        let mut current = Vec::with_capacity(update.data.len());
+        let mut remaining = update.data.clone();
+        let mut found = 0;
+
+        self.build_orderings(&mut current, &mut remaining, limit, &mut found, on_ordering);
+
+        found
+    }
+
+    fn build_orderings(
+        &self,
+        current: &mut Vec<Page>,
+        remaining: &mut Vec<Page>,
+        limit: usize,
+        found: &mut usize,
+        on_ordering: &mut dyn FnMut(&[Page]),
+    ) {
+        if *found >= limit {
+            return;
+        }
+
+        // Every page has been placed, then we have a full valid ordering
+        if remaining.is_empty() {
+            *found += 1;
+            on_ordering(current);
+            return;
+        }
+
+        for i in 0..remaining.len() {
+            let page = remaining[i];
+
+            if self.is_page_blocked(page, remaining) {
+                continue;
+            }
+
+            remaining.remove(i);
+            current.push(page);
+
+            self.build_orderings(current, remaining, limit, found, on_ordering);
+
+            current.pop();
+            remaining.insert(i, page);
+
+            if *found >= limit {
+                return;
+            }
+        }
+    }
+
+    // A page can only be placed once no remaining page has to go before it
+    fn is_page_blocked(&self, page: Page, remaining: &[Page]) -> bool {
+        remaining.iter().any(|other_page| {
+            self.update_rules
+                .get(other_page)
+                .is_some_and(|page_rules| page_rules.contains(&page))
+        })
+    }
+
+    // Pages are taken out while any of them isn't blocked by the others,
+    // the ones that are left block each other in a cycle
+    fn has_cyclic_rules(&self, update: &Update) -> bool {
+        let mut remaining = update.data.clone();
+
+        while let Some(i) = remaining
+            .iter()
+            .position(|page| !self.is_page_blocked(*page, &remaining))
+        {
+            remaining.swap_remove(i);
+        }
+
+        !remaining.is_empty()
+    }
+
     fn get_updates_result(updates: &[Update]) -> u32 {
         updates
             .iter()
@@ -198,6 +313,20 @@ fn main() {
 
     updates_checker.fix_updates(&mut checked_updates.bad_updates);
 
+    // The middle page is only well defined when the rules allow a single ordering
+    for update in updates_checker.get_ambiguous_updates(&checked_updates.bad_updates) {
+        match updates_checker.count_valid_orderings(&update, 2) {
+            0 => eprintln!(
+                "Warning: update {:?} has no valid ordering, its rules are cyclic",
+                update.data
+            ),
+            _ => eprintln!(
+                "Warning: update {:?} has more than one valid ordering, its middle page is arbitrary",
+                update.data
+            ),
+        }
+    }
+
     let fixed_updates_result = UpdatesChecker::get_updates_result(&checked_updates.bad_updates);
 
     println!("Part 2 result: {}", fixed_updates_result);
@@ -373,4 +502,93 @@ mod tests {
 
         assert_eq!(updates_result, 123);
     }
+
+    #[test]
+    fn check_unique_orderings() {
+        let input_data = get_test_input();
+
+        let updates_checker = UpdatesChecker::new(input_data);
+
+        let bad_update = Update {
+            data: vec![75, 97, 47, 61, 53],
+        };
+
+        assert!(updates_checker.has_unique_ordering(&bad_update));
+        assert_eq!(
+            updates_checker.get_valid_orderings(&bad_update, 10),
+            vec![Update {
+                data: vec![97, 75, 47, 61, 53]
+            }]
+        );
+
+        let checked_updates = updates_checker.check_updates();
+
+        assert!(updates_checker
+            .get_ambiguous_updates(&checked_updates.bad_updates)
+            .is_empty());
+    }
+
+    #[test]
+    fn check_ambiguous_orderings() {
+        let input_data = InputData {
+            ordering_rules: vec![
+                PageOrder { page: 1, before: 2 },
+                PageOrder { page: 1, before: 3 },
+            ],
+            updates: vec![Update {
+                data: vec![3, 2, 1, 4],
+            }],
+        };
+
+        let updates_checker = UpdatesChecker::new(input_data);
+        let update = updates_checker.updates[0].clone();
+
+        assert!(!updates_checker.has_unique_ordering(&update));
+        assert_eq!(
+            updates_checker.count_valid_orderings(&update, usize::MAX),
+            8
+        );
+        assert_eq!(updates_checker.count_valid_orderings(&update, 5), 5);
+
+        let orderings = updates_checker.get_valid_orderings(&update, 3);
+
+        assert_eq!(orderings.len(), 3);
+        assert!(orderings.iter().all(|x| updates_checker.is_update_good(x)));
+    }
+
+    #[test]
+    fn check_cyclic_orderings() {
+        let input_data = InputData {
+            ordering_rules: vec![
+                PageOrder { page: 1, before: 2 },
+                PageOrder { page: 2, before: 1 },
+            ],
+            updates: vec![],
+        };
+
+        let updates_checker = UpdatesChecker::new(input_data);
+
+        let update = Update { data: vec![1, 2] };
+
+        assert_eq!(
+            updates_checker.count_valid_orderings(&update, usize::MAX),
+            0
+        );
+        assert!(!updates_checker.has_unique_ordering(&update));
+
+        // Pages without rules don't hide the cycle, nor make the search go through all their orders
+        let update = Update {
+            data: (1..=20).collect(),
+        };
+
+        assert!(updates_checker.has_cyclic_rules(&update));
+        assert_eq!(updates_checker.count_valid_orderings(&update, 2), 0);
+
+        let update = Update {
+            data: (2..=20).collect(),
+        };
+
+        assert!(!updates_checker.has_cyclic_rules(&update));
+        assert_eq!(updates_checker.count_valid_orderings(&update, 2), 2);
+    }
 }
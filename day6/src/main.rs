@@ -6,6 +6,9 @@ use std::{
     io::{BufRead, BufReader},
 };
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 #[derive(Debug, PartialEq)]
 enum MapTile {
     Empty,
@@ -63,6 +66,13 @@ impl Map {
     }
 
     fn get_guards_route_distinc_tiles(&self) -> Result<HashSet<Position>, ()> {
+        self.get_guards_route_with_obstacle(None)
+    }
+
+    fn get_guards_route_with_obstacle(
+        &self,
+        extra_obstacle: Option<&Position>,
+    ) -> Result<HashSet<Position>, ()> {
         let mut distinc_positions = HashSet::new();
 
         let mut guard_pos = self.find_guard_pos().expect("Guard is not within the map");
@@ -83,7 +93,9 @@ impl Map {
             }
 
             // Check for obstacles and rotate if any
-            if self.tiles[new_pos.y as usize][new_pos.x as usize] == MapTile::Obstacle {
+            if self.tiles[new_pos.y as usize][new_pos.x as usize] == MapTile::Obstacle
+                || extra_obstacle == Some(&new_pos)
+            {
                 guard_direction = Direction::apply_90_clockwise_rotation(&guard_direction);
                 continue;
             }
@@ -111,7 +123,7 @@ impl Map {
             .len()
     }
 
-    fn get_loop_path_combinations_count(&mut self) -> usize {
+    fn get_loop_path_combinations_count(&self) -> usize {
         let mut visited_tiles = self
             .get_guards_route_distinc_tiles()
             .expect("Invalid route found");
@@ -120,20 +132,19 @@ impl Map {
         let guard_intial_pos = self.find_guard_pos().expect("Guard not found");
         visited_tiles.remove(&guard_intial_pos);
 
-        // For every tile in the path, add an obstacle and check for loops
-        let mut loop_combinations = 0;
-        for path_tile in visited_tiles {
-            self.tiles[path_tile.y as usize][path_tile.x as usize] = MapTile::Obstacle;
-
-            let route = self.get_guards_route_distinc_tiles();
-            if route.is_err() {
-                loop_combinations += 1;
-            }
-
-            self.tiles[path_tile.y as usize][path_tile.x as usize] = MapTile::Empty;
-        }
-
-        loop_combinations
+        // For every tile in the path, add an obstacle and check for loops.
+        // Each candidate is checked on its own, so the map isn't modified
+        #[cfg(feature = "parallel")]
+        let candidate_tiles = visited_tiles.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let candidate_tiles = visited_tiles.iter();
+
+        candidate_tiles
+            .filter(|path_tile| {
+                self.get_guards_route_with_obstacle(Some(path_tile))
+                    .is_err()
+            })
+            .count()
     }
 
     fn is_within_bounds(&self, x: isize, y: isize) -> bool {
@@ -142,7 +153,7 @@ impl Map {
 }
 
 fn main() {
-    let mut map = Map::parse("day6/data/input.txt");
+    let map = Map::parse("day6/data/input.txt");
 
     let distinct_tiles = map.get_guards_route_distinc_tiles_count();
 
@@ -200,7 +211,7 @@ mod tests {
 
     #[test]
     fn check_loop_obtacle_combinations() {
-        let mut input = Map::parse("data/test.txt");
+        let input = Map::parse("data/test.txt");
 
         let loop_combinations = input.get_loop_path_combinations_count();
 
@@ -1,24 +1,205 @@
 use std::{
-    collections::VecDeque,
+    fmt::{Debug, Display},
     fs::File,
     io::{BufRead, BufReader},
-    iter::repeat_n,
+    ops::BitXor,
+    str::FromStr,
+    sync::Arc,
 };
 
-use itertools::Itertools;
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Numbers the equations can be solved with. Every operation is checked, so
+// a branch that overflows is pruned instead of panicking or wrapping around
+trait Number: Clone + Debug + Display + PartialEq + FromStr + Send + Sync + 'static {
+    fn zero() -> Self;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    // None is returned when dividing by zero
+    fn checked_div_rem(&self, other: &Self) -> Option<(Self, Self)>;
+
+    fn count_digits(&self, base: u32) -> u32;
+
+    fn checked_base_pow(base: u32, exponent: u32) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn checked_exact_div(&self, other: &Self) -> Option<Self> {
+        let (quotient, remainder) = self.checked_div_rem(other)?;
+
+        remainder.is_zero().then_some(quotient)
+    }
+}
+
+impl Number for u64 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        u64::checked_sub(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *other)
+    }
+
+    fn checked_div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        Some((self.checked_div(*other)?, self.checked_rem(*other)?))
+    }
+
+    fn count_digits(&self, base: u32) -> u32 {
+        self.checked_ilog(base as u64).unwrap_or_default() + 1
+    }
+
+    fn checked_base_pow(base: u32, exponent: u32) -> Option<Self> {
+        (base as u64).checked_pow(exponent)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl Number for BigUint {
+    fn zero() -> Self {
+        BigUint::default()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if self < other {
+            return None;
+        }
+
+        Some(self - other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn checked_div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+
+        Some((self / other, self % other))
+    }
+
+    fn count_digits(&self, base: u32) -> u32 {
+        self.to_radix_le(base).len() as u32
+    }
+
+    fn checked_base_pow(base: u32, exponent: u32) -> Option<Self> {
+        Some(BigUint::from(base).pow(exponent))
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
-struct CalibrationEquation {
-    result: u64,
-    members: Vec<u64>,
+struct CalibrationEquation<N = u64> {
+    result: N,
+    members: Vec<N>,
+}
+
+impl<N: Number> CalibrationEquation<N> {
+    fn solve(&self, operators: &OperatorsList<N>) -> Option<SolvedEquation<N>> {
+        self.solve_n(operators, 1).pop()
+    }
+
+    #[allow(dead_code)]
+    fn solve_all(&self, operators: &OperatorsList<N>) -> Vec<SolvedEquation<N>> {
+        self.solve_n(operators, usize::MAX)
+    }
+
+    #[allow(dead_code)]
+    fn count_solutions(&self, operators: &OperatorsList<N>) -> usize {
+        self.solve_all(operators).len()
+    }
+
+    fn solve_n(&self, operators: &OperatorsList<N>, limit: usize) -> Vec<SolvedEquation<N>> {
+        let mut solutions = Vec::new();
+
+        // Reverse solving prunes much earlier, but it needs every operator to be undoable.
+        // A zero member might have absorbed anything before it, which can't be undone either
+        if operators.is_reversible() && !self.members.iter().any(|member| member.is_zero()) {
+            CalibrationEquations::find_solutions_backwards(
+                &self.result,
+                &self.members,
+                operators,
+                limit,
+                &mut Vec::new(),
+                &mut solutions,
+            );
+        } else {
+            CalibrationEquations::find_solutions_forwards(
+                &self.members[0],
+                &self.result,
+                &self.members[1..],
+                operators,
+                limit,
+                &mut Vec::new(),
+                &mut solutions,
+            );
+        }
+
+        solutions
+            .into_iter()
+            .map(|operators| SolvedEquation {
+                equation: self.clone(),
+                operators,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SolvedEquation<N = u64> {
+    equation: CalibrationEquation<N>,
+    operators: Vec<Arc<dyn Operator<N>>>,
+}
+
+impl<N: Number> SolvedEquation<N> {
+    #[allow(dead_code)]
+    fn uses_operator(&self, symbol: &str) -> bool {
+        self.operators
+            .iter()
+            .any(|operator| operator.symbol() == symbol)
+    }
+}
+
+impl<N: Number> Display for SolvedEquation<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.equation.result, self.equation.members[0])?;
+
+        for (operator, member) in self.operators.iter().zip(&self.equation.members[1..]) {
+            write!(f, " {} {}", operator.symbol(), member)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
-struct CalibrationEquations {
-    data: Vec<CalibrationEquation>,
+struct CalibrationEquations<N = u64> {
+    data: Vec<CalibrationEquation<N>>,
 }
 
-impl CalibrationEquations {
+impl<N: Number> CalibrationEquations<N> {
     fn parse(file_path: &str) -> Self {
         let file = File::open(file_path).expect("File can't be read");
         let reader = BufReader::new(file);
@@ -31,13 +212,16 @@ impl CalibrationEquations {
             let result_members = line.split(": ").take(2).collect::<Vec<&str>>();
 
             let result = result_members[0]
-                .parse::<u64>()
-                .expect("Can't parse eq result to number");
+                .parse::<N>()
+                .unwrap_or_else(|_| panic!("Can't parse eq result to number"));
 
             let members = result_members[1]
                 .split(" ")
-                .map(|x| x.parse::<u64>().expect("Can't parse eq member to number"))
-                .collect::<Vec<u64>>();
+                .map(|x| {
+                    x.parse::<N>()
+                        .unwrap_or_else(|_| panic!("Can't parse eq member to number"))
+                })
+                .collect::<Vec<N>>();
 
             equations.push(CalibrationEquation { result, members });
         }
@@ -45,96 +229,363 @@ impl CalibrationEquations {
         Self { data: equations }
     }
 
-    fn get_possible_equations(&self, skip_combination: bool) -> Self {
-        let mut possible_equations = Vec::new();
+    fn get_possible_equations(&self, operators: &OperatorsList<N>) -> Self {
+        let possible_equations = self
+            .get_solved_equations(operators)
+            .into_iter()
+            .map(|solved_equation| solved_equation.equation)
+            .collect();
 
-        'equation: for equation in self.data.iter() {
-            let operators_list = if skip_combination {
-                Operator::get_basic_operators_list()
-            } else {
-                Operator::get_total_operators_list()
-            };
+        Self {
+            data: possible_equations,
+        }
+    }
+
+    fn get_solved_equations(&self, operators: &OperatorsList<N>) -> Vec<SolvedEquation<N>> {
+        // Equations are independent from each other, and the original order is kept
+        #[cfg(feature = "parallel")]
+        let equations = self.data.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let equations = self.data.iter();
 
-            let operators_combinations =
-                Operator::get_operators_combinations(operators_list, equation.members.len() - 1);
+        equations
+            .filter_map(|equation| equation.solve(operators))
+            .collect()
+    }
 
-            for operators_combination in operators_combinations {
-                let mut eq_numbers = VecDeque::from(equation.members.clone());
-                let mut operators = operators_combination.clone();
+    #[cfg(test)]
+    fn is_equation_possible(result: N, members: &[N], operators: &OperatorsList<N>) -> bool {
+        let equation = CalibrationEquation {
+            result,
+            members: members.to_vec(),
+        };
 
-                // Reverse operators to get right order when doing pop from the back
-                operators.reverse();
+        equation.solve(operators).is_some()
+    }
 
-                while eq_numbers.len() > 1 {
-                    let first_number = eq_numbers.pop_front().expect("Number vec is empty");
-                    let second_number = eq_numbers.pop_front().expect("Number vec is empty");
+    fn find_solutions_backwards(
+        result: &N,
+        members: &[N],
+        operators: &OperatorsList<N>,
+        limit: usize,
+        current_operators: &mut Vec<Arc<dyn Operator<N>>>,
+        solutions: &mut Vec<Vec<Arc<dyn Operator<N>>>>,
+    ) {
+        if solutions.len() >= limit {
+            return;
+        }
 
-                    let operator = operators.pop().expect("Operator vec is empty");
+        let (last_member, previous_members) =
+            members.split_last().expect("Equation has no members");
 
-                    let result = operator.resolve(first_number, second_number);
+        // Only the first member is left, so it must match what remains of the result.
+        // Operators were collected from last to first, so they need to be reversed
+        if previous_members.is_empty() {
+            if last_member == result {
+                solutions.push(current_operators.iter().rev().cloned().collect());
+            }
+            return;
+        }
 
-                    eq_numbers.push_front(result);
-                }
+        // Operators are evaluated left to right, so the last one applied can be undone first.
+        // Any operator that can't be undone prunes the whole branch
+        for operator in operators.0.iter() {
+            let Some(previous_result) = operator.unresolve(result, last_member) else {
+                continue;
+            };
 
-                // Check result, if valid no more combinations are needed for it
-                if eq_numbers[0] == equation.result {
-                    possible_equations.push(equation.clone());
-                    continue 'equation;
-                }
+            current_operators.push(operator.clone());
+
+            Self::find_solutions_backwards(
+                &previous_result,
+                previous_members,
+                operators,
+                limit,
+                current_operators,
+                solutions,
+            );
+
+            current_operators.pop();
+
+            if solutions.len() >= limit {
+                return;
             }
         }
+    }
 
-        Self {
-            data: possible_equations,
+    fn find_solutions_forwards(
+        partial_result: &N,
+        result: &N,
+        members: &[N],
+        operators: &OperatorsList<N>,
+        limit: usize,
+        current_operators: &mut Vec<Arc<dyn Operator<N>>>,
+        solutions: &mut Vec<Vec<Arc<dyn Operator<N>>>>,
+    ) {
+        if solutions.len() >= limit {
+            return;
+        }
+
+        // All the members have been used, check if we got the expected result
+        let Some((next_member, next_members)) = members.split_first() else {
+            if partial_result == result {
+                solutions.push(current_operators.clone());
+            }
+            return;
+        };
+
+        // Any operator that can't be resolved, or overflows, prunes the whole branch
+        for operator in operators.0.iter() {
+            let Some(next_result) = operator.resolve(partial_result, next_member) else {
+                continue;
+            };
+
+            current_operators.push(operator.clone());
+
+            Self::find_solutions_forwards(
+                &next_result,
+                result,
+                next_members,
+                operators,
+                limit,
+                current_operators,
+                solutions,
+            );
+
+            current_operators.pop();
+
+            if solutions.len() >= limit {
+                return;
+            }
         }
     }
 
-    fn get_equations_total_calibration_result(&self) -> u64 {
-        self.data.iter().map(|x| x.result).sum()
+    fn get_equations_total_calibration_result(&self) -> N {
+        self.data.iter().fold(N::zero(), |total, x| {
+            total
+                .checked_add(&x.result)
+                .expect("Total calibration result overflowed")
+        })
+    }
+}
+
+trait Operator<N>: Debug + Send + Sync {
+    fn symbol(&self) -> &str;
+
+    // None is returned when the operation isn't valid for the given numbers
+    fn resolve(&self, number_a: &N, number_b: &N) -> Option<N>;
+
+    // Find number_a back from the result, only available for reversible operators
+    fn unresolve(&self, _result: &N, _number_b: &N) -> Option<N> {
+        None
+    }
+
+    fn is_reversible(&self) -> bool {
+        false
     }
 }
 
 #[derive(Clone, Debug)]
-enum Operator {
-    Sum,
-    Multiplication,
-    Combination,
+struct OperatorsList<N = u64>(Vec<Arc<dyn Operator<N>>>);
+
+impl<N: Number> OperatorsList<N> {
+    fn new(operators: Vec<Arc<dyn Operator<N>>>) -> Self {
+        Self(operators)
+    }
+
+    fn get_basic_operators_list() -> Self {
+        Self::new(vec![Arc::new(Sum), Arc::new(Multiplication)])
+    }
+
+    fn get_total_operators_list() -> Self {
+        Self::new(vec![
+            Arc::new(Sum),
+            Arc::new(Multiplication),
+            Arc::new(BaseCombination { base: 10 }),
+        ])
+    }
+
+    fn is_reversible(&self) -> bool {
+        self.0.iter().all(|operator| operator.is_reversible())
+    }
 }
 
-impl Operator {
-    fn get_basic_operators_list() -> Vec<Self> {
-        Vec::from([Self::Sum, Self::Multiplication])
+#[derive(Debug)]
+struct Sum;
+
+impl<N: Number> Operator<N> for Sum {
+    fn symbol(&self) -> &str {
+        "+"
     }
 
-    fn get_total_operators_list() -> Vec<Self> {
-        Vec::from([Self::Sum, Self::Multiplication, Self::Combination])
+    fn resolve(&self, number_a: &N, number_b: &N) -> Option<N> {
+        number_a.checked_add(number_b)
     }
 
-    fn get_operators_combinations(operators: Vec<Self>, size: usize) -> Vec<Vec<Operator>> {
-        repeat_n(operators, size)
-            .multi_cartesian_product()
-            .collect()
+    fn unresolve(&self, result: &N, number_b: &N) -> Option<N> {
+        result.checked_sub(number_b)
+    }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug)]
+struct Multiplication;
+
+impl<N: Number> Operator<N> for Multiplication {
+    fn symbol(&self) -> &str {
+        "*"
+    }
+
+    fn resolve(&self, number_a: &N, number_b: &N) -> Option<N> {
+        number_a.checked_mul(number_b)
+    }
+
+    fn unresolve(&self, result: &N, number_b: &N) -> Option<N> {
+        // A zero factor can't be undone, equations with zero members are solved forwards
+        result.checked_exact_div(number_b)
+    }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Subtraction;
+
+impl<N: Number> Operator<N> for Subtraction {
+    fn symbol(&self) -> &str {
+        "-"
+    }
+
+    fn resolve(&self, number_a: &N, number_b: &N) -> Option<N> {
+        number_a.checked_sub(number_b)
+    }
+
+    fn unresolve(&self, result: &N, number_b: &N) -> Option<N> {
+        result.checked_add(number_b)
+    }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Division;
+
+impl<N: Number> Operator<N> for Division {
+    fn symbol(&self) -> &str {
+        "/"
+    }
+
+    fn resolve(&self, number_a: &N, number_b: &N) -> Option<N> {
+        // Only exact divisions are allowed
+        number_a.checked_exact_div(number_b)
+    }
+
+    fn unresolve(&self, result: &N, number_b: &N) -> Option<N> {
+        if number_b.is_zero() {
+            return None;
+        }
+
+        result.checked_mul(number_b)
+    }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Xor;
+
+// Only numbers with a bitwise representation can be xored
+impl<N: Number> Operator<N> for Xor
+where
+    for<'a> &'a N: BitXor<&'a N, Output = N>,
+{
+    fn symbol(&self) -> &str {
+        "^"
+    }
+
+    fn resolve(&self, number_a: &N, number_b: &N) -> Option<N> {
+        Some(number_a ^ number_b)
+    }
+
+    fn unresolve(&self, result: &N, number_b: &N) -> Option<N> {
+        Some(result ^ number_b)
+    }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
+}
+
+// Concatenation of the digits of both numbers written in the given base
+#[derive(Debug)]
+struct BaseCombination {
+    base: u32,
+}
+
+impl BaseCombination {
+    fn get_suffix_size<N: Number>(&self, number: &N) -> Option<N> {
+        N::checked_base_pow(self.base, number.count_digits(self.base))
+    }
+}
+
+impl<N: Number> Operator<N> for BaseCombination {
+    fn symbol(&self) -> &str {
+        "||"
     }
 
-    fn resolve(&self, number_a: u64, number_b: u64) -> u64 {
-        match &self {
-            Operator::Sum => number_a + number_b,
-            Operator::Multiplication => number_a * number_b,
-            Operator::Combination => format!("{}{}", number_a, number_b).parse().unwrap(),
+    fn resolve(&self, number_a: &N, number_b: &N) -> Option<N> {
+        let suffix_size = self.get_suffix_size(number_b)?;
+
+        number_a.checked_mul(&suffix_size)?.checked_add(number_b)
+    }
+
+    fn unresolve(&self, result: &N, number_b: &N) -> Option<N> {
+        let suffix_size = self.get_suffix_size(number_b)?;
+
+        let (prefix, suffix) = result.checked_div_rem(&suffix_size)?;
+
+        if suffix != *number_b {
+            return None;
         }
+
+        Some(prefix)
+    }
+
+    fn is_reversible(&self) -> bool {
+        true
     }
 }
 
+// Targets bigger than u64 need the arbitrary precision numbers
+#[cfg(not(feature = "bigint"))]
+type InputNumber = u64;
+#[cfg(feature = "bigint")]
+type InputNumber = BigUint;
+
 fn main() {
-    let calibration_equations = CalibrationEquations::parse("day7/data/input.txt");
+    let calibration_equations = CalibrationEquations::<InputNumber>::parse("day7/data/input.txt");
 
-    let possible_equations = calibration_equations.get_possible_equations(true);
+    let possible_equations =
+        calibration_equations.get_possible_equations(&OperatorsList::get_basic_operators_list());
 
     let calibration_total_result = possible_equations.get_equations_total_calibration_result();
 
     println!("Part 1 result: {}", calibration_total_result);
 
-    let possible_equations = calibration_equations.get_possible_equations(false);
+    let possible_equations =
+        calibration_equations.get_possible_equations(&OperatorsList::get_total_operators_list());
 
     let calibration_total_result = possible_equations.get_equations_total_calibration_result();
 
@@ -202,7 +653,8 @@ mod tests {
     fn check_possible_equations_count() {
         let test_data = get_test_input();
 
-        let possible_equations = test_data.get_possible_equations(true);
+        let possible_equations =
+            test_data.get_possible_equations(&OperatorsList::get_basic_operators_list());
 
         let calibration_total_result = possible_equations.get_equations_total_calibration_result();
 
@@ -213,10 +665,227 @@ mod tests {
     fn check_possible_equations_with_combinations_count() {
         let test_data = get_test_input();
 
-        let possible_equations = test_data.get_possible_equations(false);
+        let possible_equations =
+            test_data.get_possible_equations(&OperatorsList::get_total_operators_list());
 
         let calibration_total_result = possible_equations.get_equations_total_calibration_result();
 
         assert_eq!(calibration_total_result, 11387);
     }
+
+    #[test]
+    fn check_operators_unresolve() {
+        assert_eq!(Sum.unresolve(&29_u64, &19), Some(10));
+        assert_eq!(Sum.unresolve(&10_u64, &19), None);
+
+        assert_eq!(Multiplication.unresolve(&190_u64, &19), Some(10));
+        assert_eq!(Multiplication.unresolve(&191_u64, &19), None);
+
+        let combination = BaseCombination { base: 10 };
+
+        assert_eq!(combination.unresolve(&156_u64, &6), Some(15));
+        assert_eq!(combination.unresolve(&1510_u64, &10), Some(15));
+        assert_eq!(combination.unresolve(&1511_u64, &10), None);
+        assert_eq!(
+            combination.unresolve(&u64::MAX, &615),
+            Some(18446744073709551)
+        );
+    }
+
+    #[test]
+    fn check_long_equation() {
+        let members: Vec<u64> = vec![
+            3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73,
+        ];
+
+        assert!(CalibrationEquations::is_equation_possible(
+            710,
+            &members,
+            &OperatorsList::get_total_operators_list()
+        ));
+        assert!(!CalibrationEquations::is_equation_possible(
+            711,
+            &members,
+            &OperatorsList::get_total_operators_list()
+        ));
+    }
+
+    #[test]
+    fn check_solved_equations() {
+        let test_data = get_test_input();
+
+        let solved_equations =
+            test_data.get_solved_equations(&OperatorsList::get_total_operators_list());
+
+        let printed_equations: Vec<String> =
+            solved_equations.iter().map(|x| x.to_string()).collect();
+
+        assert_eq!(
+            printed_equations,
+            vec![
+                "190 = 10 * 19",
+                "3267 = 81 * 40 + 27",
+                "156 = 15 || 6",
+                "7290 = 6 * 8 || 6 * 15",
+                "192 = 17 || 8 + 14",
+                "292 = 11 + 6 * 16 + 20",
+            ]
+        );
+
+        let combination_equations = solved_equations
+            .iter()
+            .filter(|x| x.uses_operator("||"))
+            .count();
+
+        assert_eq!(combination_equations, 3);
+    }
+
+    #[test]
+    fn check_equation_solutions_count() {
+        let test_data = get_test_input();
+
+        let operators = OperatorsList::get_basic_operators_list();
+
+        assert_eq!(test_data.data[1].count_solutions(&operators), 2);
+        assert_eq!(test_data.data[2].count_solutions(&operators), 0);
+
+        let solutions: Vec<String> = test_data.data[1]
+            .solve_all(&operators)
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        assert_eq!(
+            solutions,
+            vec!["3267 = 81 * 40 + 27", "3267 = 81 + 40 * 27"]
+        );
+    }
+
+    #[derive(Debug)]
+    struct Max;
+
+    impl Operator<u64> for Max {
+        fn symbol(&self) -> &str {
+            "max"
+        }
+
+        fn resolve(&self, number_a: &u64, number_b: &u64) -> Option<u64> {
+            Some(*number_a.max(number_b))
+        }
+    }
+
+    #[test]
+    fn check_custom_operators() {
+        let equation = CalibrationEquation {
+            result: 5,
+            members: vec![12, 4, 3, 6],
+        };
+
+        let operators = OperatorsList::new(vec![
+            Arc::new(Subtraction),
+            Arc::new(Division),
+            Arc::new(Xor),
+        ]);
+
+        assert!(operators.is_reversible());
+        assert_eq!(
+            equation.solve(&operators).map(|x| x.to_string()),
+            Some(String::from("5 = 12 - 4 ^ 3 - 6"))
+        );
+
+        let operators = OperatorsList::new(vec![Arc::new(Sum), Arc::new(Max)]);
+
+        let equation = CalibrationEquation {
+            result: 20,
+            members: vec![3, 9, 4, 7],
+        };
+
+        assert!(!operators.is_reversible());
+        assert_eq!(
+            equation
+                .solve_all(&operators)
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+            vec!["20 = 3 max 9 + 4 + 7"]
+        );
+    }
+
+    #[test]
+    fn check_base_combination() {
+        let binary_combination = BaseCombination { base: 2 };
+
+        assert_eq!(binary_combination.resolve(&0b101_u64, &0b11), Some(0b10111));
+        assert_eq!(
+            binary_combination.unresolve(&0b10111_u64, &0b11),
+            Some(0b101)
+        );
+        assert_eq!(binary_combination.unresolve(&0b10110_u64, &0b11), None);
+
+        let decimal_combination = BaseCombination { base: 10 };
+
+        assert_eq!(decimal_combination.resolve(&15_u64, &6), Some(156));
+    }
+
+    #[test]
+    fn check_overflow_is_pruned() {
+        assert_eq!(Sum.resolve(&u64::MAX, &1), None);
+        assert_eq!(Multiplication.resolve(&u64::MAX, &2), None);
+        let combination = BaseCombination { base: 10 };
+
+        assert_eq!(combination.resolve(&u64::MAX, &1), None);
+        assert_eq!(
+            combination.resolve(&1844674407370955161_u64, &5),
+            Some(u64::MAX)
+        );
+
+        let equation = CalibrationEquation {
+            result: u64::MAX,
+            members: vec![u64::MAX, 2, 1],
+        };
+
+        assert_eq!(
+            equation.count_solutions(&OperatorsList::get_total_operators_list()),
+            0
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn check_big_numbers() {
+        let big_number = |x: &str| x.parse::<BigUint>().unwrap();
+
+        let equation = CalibrationEquation {
+            result: big_number("18446744073709551615184467440737095516150"),
+            members: vec![
+                big_number("18446744073709551615"),
+                big_number("18446744073709551615"),
+                big_number("10"),
+            ],
+        };
+
+        assert_eq!(
+            equation
+                .solve(&OperatorsList::get_total_operators_list())
+                .map(|x| x.to_string()),
+            Some(String::from(
+                "18446744073709551615184467440737095516150 = 18446744073709551615 || 18446744073709551615 * 10"
+            ))
+        );
+    }
+
+    #[test]
+    fn check_zero_member() {
+        let equation = CalibrationEquation {
+            result: 7,
+            members: vec![5, 0, 7],
+        };
+        let operators = OperatorsList::get_total_operators_list();
+
+        assert_eq!(
+            equation.solve(&operators).unwrap().to_string(),
+            "7 = 5 * 0 + 7"
+        );
+        assert_eq!(equation.count_solutions(&operators), 2);
+    }
 }
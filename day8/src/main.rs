@@ -6,9 +6,45 @@ use std::{
     io::{BufRead, BufReader},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AntinodesMode {
+    // Only one antinode at twice the distance
+    Single,
+    // Antinodes at every multiple of the distance, antennas included
+    ResonantHarmonics,
+    // Every grid point on the line, including the ones between antennas
+    ExactLine,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AntinodeSource {
+    frequency: char,
+    base_antenna: Position,
+    next_antenna: Position,
+}
+
+// Puzzle examples mark antinodes with '#', which must not be taken as an antenna
+const DEFAULT_EMPTY_TILES: [char; 2] = ['.', '#'];
+#[allow(dead_code)]
+const ANTINODE_MARKER: char = '#';
+
+#[derive(Debug, PartialEq)]
+struct AntinodesVerification {
+    missing: HashSet<Position>,
+    unexpected: HashSet<Position>,
+}
+
+impl AntinodesVerification {
+    #[allow(dead_code)]
+    fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct AntennasMap {
     data: Vec<Vec<char>>,
+    empty_tiles: Vec<char>,
 }
 
 impl AntennasMap {
@@ -26,17 +62,28 @@ impl AntennasMap {
             words_board_data.push(line_data);
         }
 
+        Self::new(words_board_data)
+    }
+
+    fn new(data: Vec<Vec<char>>) -> Self {
         Self {
-            data: words_board_data,
+            data,
+            empty_tiles: DEFAULT_EMPTY_TILES.to_vec(),
         }
     }
 
+    #[allow(dead_code)]
+    fn with_empty_tiles(mut self, empty_tiles: &[char]) -> Self {
+        self.empty_tiles = empty_tiles.to_vec();
+        self
+    }
+
     fn get_antennas_grouped_by_frequency(&self) -> HashMap<char, Vec<Position>> {
         let mut antennas: HashMap<char, Vec<Position>> = HashMap::new();
 
         for (y, line) in self.data.iter().enumerate() {
             for (x, &frequency) in line.iter().enumerate() {
-                if frequency == '.' {
+                if self.empty_tiles.contains(&frequency) {
                     continue;
                 }
 
@@ -58,48 +105,158 @@ impl AntennasMap {
         antennas
     }
 
-    fn get_unique_antinodes_count(&self, use_resonant_harmonics: bool) -> usize {
-        let mut antinodes: HashSet<Position> = HashSet::new();
-
-        let antennas_groups = self.get_antennas_grouped_by_frequency();
+    fn get_unique_antinodes_count(&self, mode: AntinodesMode) -> usize {
+        self.get_antinodes_sources(mode).len()
+    }
 
-        for (_, antennas) in antennas_groups.iter() {
-            // Compare each antenna againt the others
-            for current_antenna in antennas {
-                for next_antenna in antennas {
-                    // Don't compare an antenna against itself
-                    if current_antenna == next_antenna {
-                        continue;
-                    }
+    fn get_antinodes_sources(&self, mode: AntinodesMode) -> HashMap<Position, Vec<AntinodeSource>> {
+        let mut antinodes: HashMap<Position, Vec<AntinodeSource>> = HashMap::new();
 
-                    // Get generated antinodes
-                    let calculated_antinodes = self.calculate_antinodes(
-                        current_antenna,
-                        next_antenna,
-                        use_resonant_harmonics,
-                    );
+        let antennas_groups = self.get_antennas_grouped_by_frequency();
 
-                    // Try to add the antinode
+        for (&frequency, antennas) in antennas_groups.iter() {
+            // Compare each pair of antennas only once, the base antenna being the first one found
+            for (i, current_antenna) in antennas.iter().enumerate() {
+                for next_antenna in antennas[i + 1..].iter() {
+                    // Get generated antinodes on both sides, as some modes
+                    // generate the same antinodes from both antennas
+                    let calculated_antinodes: HashSet<Position> = self
+                        .calculate_antinodes(current_antenna, next_antenna, mode)
+                        .into_iter()
+                        .chain(self.calculate_antinodes(next_antenna, current_antenna, mode))
+                        .collect();
+
+                    // Keep track of which pair generated each antinode
                     for antinode in calculated_antinodes {
-                        antinodes.insert(antinode);
+                        antinodes.entry(antinode).or_default().push(AntinodeSource {
+                            frequency,
+                            base_antenna: current_antenna.clone(),
+                            next_antenna: next_antenna.clone(),
+                        });
                     }
                 }
             }
         }
 
-        antinodes.len()
+        antinodes
+    }
+
+    #[allow(dead_code)]
+    fn get_antinodes_count_by_frequency(&self, mode: AntinodesMode) -> HashMap<char, usize> {
+        let mut frequencies_count: HashMap<char, usize> = HashMap::new();
+
+        for sources in self.get_antinodes_sources(mode).values() {
+            // The same frequency can generate an antinode from several pairs
+            let frequencies: HashSet<char> = sources.iter().map(|x| x.frequency).collect();
+
+            for frequency in frequencies {
+                *frequencies_count.entry(frequency).or_default() += 1;
+            }
+        }
+
+        frequencies_count
+    }
+
+    #[allow(dead_code)]
+    fn get_overlapping_antinodes(&self, mode: AntinodesMode) -> HashMap<Position, HashSet<char>> {
+        self.get_antinodes_sources(mode)
+            .into_iter()
+            .map(|(antinode, sources)| {
+                let frequencies: HashSet<char> = sources.iter().map(|x| x.frequency).collect();
+
+                (antinode, frequencies)
+            })
+            .filter(|(_, frequencies)| frequencies.len() > 1)
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    fn get_marked_positions(&self, marker: char) -> HashSet<Position> {
+        self.data
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.iter()
+                    .enumerate()
+                    .filter(|(_, &tile)| tile == marker)
+                    .map(move |(x, _)| Position {
+                        x: x as isize,
+                        y: y as isize,
+                    })
+            })
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    fn verify_marked_antinodes(&self, mode: AntinodesMode, marker: char) -> AntinodesVerification {
+        let marked_antinodes = self.get_marked_positions(marker);
+
+        let antennas: HashSet<Position> = self
+            .get_antennas_grouped_by_frequency()
+            .into_values()
+            .flatten()
+            .collect();
+
+        let computed_antinodes: HashSet<Position> =
+            self.get_antinodes_sources(mode).into_keys().collect();
+
+        // Antinodes on top of an antenna are drawn as the antenna, so they can't be checked
+        let unexpected = computed_antinodes
+            .iter()
+            .filter(|antinode| !marked_antinodes.contains(antinode) && !antennas.contains(antinode))
+            .cloned()
+            .collect();
+
+        let missing = marked_antinodes
+            .difference(&computed_antinodes)
+            .cloned()
+            .collect();
+
+        AntinodesVerification {
+            missing,
+            unexpected,
+        }
     }
 
     fn calculate_antinodes(
         &self,
         base_antenna: &Position,
         next_antenna: &Position,
-        use_resonant_harmonics: bool,
+        mode: AntinodesMode,
     ) -> Vec<Position> {
         let mut antinodes = Vec::new();
 
-        let delta_x = base_antenna.x - next_antenna.x;
-        let delta_y = base_antenna.y - next_antenna.y;
+        let mut delta_x = base_antenna.x - next_antenna.x;
+        let mut delta_y = base_antenna.y - next_antenna.y;
+
+        // Reduce the step so no grid point lying on the line is skipped. Starting
+        // from the base antenna, the points between both antennas are also covered
+        if mode == AntinodesMode::ExactLine {
+            let divisor = Self::gcd(delta_x, delta_y);
+
+            delta_x /= divisor;
+            delta_y /= divisor;
+
+            let mut multiplier = 0;
+            loop {
+                let antinode = Position {
+                    x: base_antenna.x - (delta_x * multiplier),
+                    y: base_antenna.y - (delta_y * multiplier),
+                };
+
+                if !self.is_within_bounds(antinode.x, antinode.y) {
+                    break;
+                }
+
+                antinodes.push(antinode);
+
+                multiplier += 1;
+            }
+
+            return antinodes;
+        }
+
+        let use_resonant_harmonics = mode == AntinodesMode::ResonantHarmonics;
 
         // Antinodes are located twice the distance difference, then three times, and so on
         let mut multiplier = 2;
@@ -133,6 +290,16 @@ impl AntennasMap {
         antinodes
     }
 
+    fn gcd(a: isize, b: isize) -> isize {
+        let (mut a, mut b) = (a.abs(), b.abs());
+
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+
+        a
+    }
+
     fn is_within_bounds(&self, x: isize, y: isize) -> bool {
         x >= 0 && x < self.data[0].len() as isize && y >= 0 && y < self.data.len() as isize
     }
@@ -141,11 +308,12 @@ impl AntennasMap {
 fn main() {
     let antennas_map = AntennasMap::parse("day8/data/input.txt");
 
-    let unique_antinodes = antennas_map.get_unique_antinodes_count(false);
+    let unique_antinodes = antennas_map.get_unique_antinodes_count(AntinodesMode::Single);
 
     println!("Part 1 result: {}", unique_antinodes);
 
-    let unique_antinodes = antennas_map.get_unique_antinodes_count(true);
+    let unique_antinodes =
+        antennas_map.get_unique_antinodes_count(AntinodesMode::ResonantHarmonics);
 
     println!("Part 2 result: {}", unique_antinodes);
 }
@@ -156,22 +324,20 @@ mod tests {
     use super::*;
 
     fn get_test_input() -> AntennasMap {
-        AntennasMap {
-            data: vec![
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', '0', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '0', '.', '.', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '0', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '0', '.', '.', '.', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', 'A', '.', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', 'A', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', 'A', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
-                vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
-            ],
-        }
+        AntennasMap::new(vec![
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', '0', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '0', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '0', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '0', '.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', 'A', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', 'A', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', 'A', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
+        ])
     }
 
     #[test]
@@ -187,13 +353,167 @@ mod tests {
     fn check_unique_antinodes_count() {
         let test_data = get_test_input();
 
-        assert_eq!(test_data.get_unique_antinodes_count(false), 14);
+        assert_eq!(
+            test_data.get_unique_antinodes_count(AntinodesMode::Single),
+            14
+        );
     }
 
     #[test]
     fn check_harmonics_unique_antinodes_count() {
         let test_data = get_test_input();
 
-        assert_eq!(test_data.get_unique_antinodes_count(true), 34);
+        assert_eq!(
+            test_data.get_unique_antinodes_count(AntinodesMode::ResonantHarmonics),
+            34
+        );
+    }
+
+    #[test]
+    fn check_exact_line_unique_antinodes_count() {
+        let test_data = get_test_input();
+
+        // No pair of antennas has a delta with a common factor
+        assert_eq!(
+            test_data.get_unique_antinodes_count(AntinodesMode::ExactLine),
+            34
+        );
+
+        let test_data = AntennasMap::new(vec![
+            vec!['a', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', 'a', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.'],
+            vec!['.', '.', '.', '.', '.', '.', '.'],
+        ]);
+
+        // Harmonics only land on the antennas, as the next multiple is out of bounds
+        assert_eq!(
+            test_data.get_unique_antinodes_count(AntinodesMode::ResonantHarmonics),
+            2
+        );
+
+        // The reduced step also finds (1, 2) between both antennas and (3, 6) beyond them
+        let mut antinodes = test_data.calculate_antinodes(
+            &Position { x: 0, y: 0 },
+            &Position { x: 2, y: 4 },
+            AntinodesMode::ExactLine,
+        );
+        antinodes.sort_by_key(|antinode| (antinode.x, antinode.y));
+
+        assert_eq!(
+            antinodes,
+            vec![
+                Position { x: 0, y: 0 },
+                Position { x: 1, y: 2 },
+                Position { x: 2, y: 4 },
+                Position { x: 3, y: 6 },
+            ]
+        );
+        assert_eq!(
+            test_data.get_unique_antinodes_count(AntinodesMode::ExactLine),
+            4
+        );
+    }
+
+    #[test]
+    fn check_antinodes_sources() {
+        let test_data = get_test_input();
+
+        let antinodes_sources = test_data.get_antinodes_sources(AntinodesMode::Single);
+
+        assert_eq!(antinodes_sources.len(), 14);
+        assert_eq!(
+            antinodes_sources.get(&Position { x: 6, y: 0 }),
+            Some(&vec![AntinodeSource {
+                frequency: '0',
+                base_antenna: Position { x: 5, y: 2 },
+                next_antenna: Position { x: 4, y: 4 },
+            }])
+        );
+
+        let frequencies_count = test_data.get_antinodes_count_by_frequency(AntinodesMode::Single);
+
+        assert_eq!(frequencies_count.get(&'0'), Some(&10));
+        assert_eq!(frequencies_count.get(&'A'), Some(&5));
+
+        let overlapping_antinodes = test_data.get_overlapping_antinodes(AntinodesMode::Single);
+
+        assert_eq!(
+            overlapping_antinodes,
+            HashMap::from([(Position { x: 3, y: 1 }, HashSet::from(['0', 'A']))])
+        );
+    }
+
+    #[test]
+    fn check_exact_line_antinodes_sources() {
+        let test_data = AntennasMap::new(vec![
+            "a.....".chars().collect(),
+            "......".chars().collect(),
+            "..a...".chars().collect(),
+            "......".chars().collect(),
+            "....a.".chars().collect(),
+        ]);
+
+        let antinodes_sources = test_data.get_antinodes_sources(AntinodesMode::ExactLine);
+
+        // Every antenna lies on the line of the other two, but each pair is credited once
+        assert_eq!(antinodes_sources.len(), 5);
+        assert_eq!(
+            antinodes_sources.get(&Position { x: 1, y: 1 }),
+            Some(&vec![
+                AntinodeSource {
+                    frequency: 'a',
+                    base_antenna: Position { x: 0, y: 0 },
+                    next_antenna: Position { x: 2, y: 2 },
+                },
+                AntinodeSource {
+                    frequency: 'a',
+                    base_antenna: Position { x: 0, y: 0 },
+                    next_antenna: Position { x: 4, y: 4 },
+                },
+                AntinodeSource {
+                    frequency: 'a',
+                    base_antenna: Position { x: 2, y: 2 },
+                    next_antenna: Position { x: 4, y: 4 },
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn check_empty_tiles() {
+        let test_data = AntennasMap::parse("data/test_antinodes.txt");
+
+        let antennas = test_data.get_antennas_grouped_by_frequency();
+
+        assert_eq!(antennas.len(), 2);
+        assert_eq!(antennas.get(&'0').map(|x| x.len()), Some(4));
+        assert_eq!(antennas.get(&'A').map(|x| x.len()), Some(3));
+
+        let test_data = test_data.with_empty_tiles(&['.']);
+
+        assert_eq!(test_data.get_antennas_grouped_by_frequency().len(), 3);
+    }
+
+    #[test]
+    fn check_marked_antinodes() {
+        let test_data = AntennasMap::parse("data/test_antinodes.txt");
+
+        assert_eq!(test_data.get_marked_positions(ANTINODE_MARKER).len(), 13);
+
+        let verification =
+            test_data.verify_marked_antinodes(AntinodesMode::Single, ANTINODE_MARKER);
+
+        assert!(verification.is_valid());
+
+        let verification =
+            test_data.verify_marked_antinodes(AntinodesMode::ResonantHarmonics, ANTINODE_MARKER);
+
+        assert!(!verification.is_valid());
+        assert!(verification.missing.is_empty());
+        assert_eq!(verification.unexpected.len(), 14);
     }
 }
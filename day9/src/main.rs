@@ -1,10 +1,36 @@
-use std::{fs::File, io::Read};
-
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Display,
+    fs::File,
+    io::Read,
+    ops::Range,
+};
+
+#[derive(Debug, PartialEq)]
 struct DiskMap {
     data: Vec<u32>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug, PartialEq)]
+enum DiskMapEncodingError {
+    // File ids are implicit in a disk map, so they must appear in increasing order
+    UnorderedFileIds,
+    // A disk map digit can't describe more than 9 blocks
+    RunTooLong,
+}
+
+#[derive(Debug, PartialEq)]
+enum DiskBlocksParsingError {
+    // Dump runs are written as "id*length" or ".*length"
+    MalformedRun(String),
+    // JSON blocks are an array of ids, with null for the empty ones
+    NotAnArray,
+    InvalidId(String),
+    InvalidLength(String),
+}
+
+#[derive(Clone, PartialEq, Debug)]
 struct DiskBlocks {
     data: Vec<DiskBlock>,
 }
@@ -15,27 +41,342 @@ enum DiskBlock {
     Empty,
 }
 
-impl From<DiskMap> for DiskBlocks {
+#[derive(Clone, PartialEq, Debug)]
+struct FileSpan {
+    id: u32,
+    start: u64,
+    length: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct FreeSpan {
+    start: u64,
+    length: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum CompactionStrategy {
+    // Single blocks fill the leftmost free spaces
+    Block,
+    // Whole files go to the leftmost free span that fits them
+    FirstFit,
+    // Whole files go to the smallest free span that fits them
+    BestFit,
+    // Whole files go to the biggest free span available
+    WorstFit,
+    // Like first fit, but files that don't fit whole are split into the leftmost free spans
+    SplitWhenNeeded,
+}
+
+impl CompactionStrategy {
+    fn generate_strategies_list() -> [Self; 5] {
+        [
+            Self::Block,
+            Self::FirstFit,
+            Self::BestFit,
+            Self::WorstFit,
+            Self::SplitWhenNeeded,
+        ]
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct CompactionReport {
+    checksum: u64,
+    moves: usize,
+    failed_moves: usize,
+    stats: DiskStats,
+}
+
+impl Display for CompactionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "checksum {}, {} moves, {} failed moves, {}",
+            self.checksum, self.moves, self.failed_moves, self.stats
+        )
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct DiskStats {
+    used_blocks: u64,
+    free_blocks: u64,
+    free_gaps: usize,
+    largest_free_run: u64,
+    file_fragments: BTreeMap<u32, usize>,
+}
+
+impl DiskStats {
+    fn get_fragmented_files_count(&self) -> usize {
+        self.file_fragments
+            .values()
+            .filter(|&&fragments| fragments > 1)
+            .count()
+    }
+}
+
+impl Display for DiskStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} used blocks, {} free blocks, {} free gaps, largest free run {}, {} fragmented files",
+            self.used_blocks,
+            self.free_blocks,
+            self.free_gaps,
+            self.largest_free_run,
+            self.get_fragmented_files_count()
+        )
+    }
+}
+
+// Free spans kept in slots sorted by their position on disk. A segment tree over the slots
+// keeps the biggest length within each range, so the leftmost span fitting a file is found in
+// logarithmic time, while a set sorted by length serves the strategies choosing by size
+struct FreeSpansIndex {
+    spans: Vec<FreeSpan>,
+    slots_starts: Vec<u64>,
+    tree: Vec<u64>,
+    leaves: usize,
+    by_length: BTreeSet<(u64, usize)>,
+}
+
+impl From<&DiskSpans> for FreeSpansIndex {
+    fn from(disk_spans: &DiskSpans) -> Self {
+        // Files get an empty slot too, which holds the space they leave behind once moved
+        let mut free_spans = disk_spans.get_free_spans();
+        free_spans.extend(
+            disk_spans
+                .files
+                .iter()
+                .filter(|file| file.length > 0)
+                .map(|file| FreeSpan {
+                    start: file.start,
+                    length: 0,
+                }),
+        );
+        free_spans.sort_by_key(|free_span| free_span.start);
+
+        let leaves = free_spans.len().next_power_of_two();
+
+        let mut index = Self {
+            slots_starts: free_spans.iter().map(|free_span| free_span.start).collect(),
+            spans: free_spans,
+            tree: vec![0; 2 * leaves],
+            leaves,
+            by_length: BTreeSet::new(),
+        };
+
+        for slot in 0..index.spans.len() {
+            index.update(slot);
+        }
+
+        index
+    }
+}
+
+impl FreeSpansIndex {
+    fn get(&self, slot: usize) -> &FreeSpan {
+        &self.spans[slot]
+    }
+
+    // Returns the slot of the chosen span starting before the given position, if any
+    fn find(&self, min_length: u64, before: u64, strategy: CompactionStrategy) -> Option<usize> {
+        // Empty slots are never chosen, even for empty files
+        let min_length = min_length.max(1);
+
+        match strategy {
+            CompactionStrategy::BestFit => self
+                .by_length
+                .range((min_length, 0)..)
+                .find(|&&(_, slot)| self.spans[slot].start < before)
+                .map(|&(_, slot)| slot),
+            CompactionStrategy::WorstFit => self
+                .by_length
+                .range((min_length, 0)..)
+                .rev()
+                .find(|&&(_, slot)| self.spans[slot].start < before)
+                .map(|&(_, slot)| slot),
+            // Any other span fitting the file would be further to the right
+            CompactionStrategy::Block
+            | CompactionStrategy::FirstFit
+            | CompactionStrategy::SplitWhenNeeded => self
+                .find_slot(1, 0, self.leaves, &(0..self.spans.len()), min_length, true)
+                .filter(|&slot| self.spans[slot].start < before),
+        }
+    }
+
+    // First or last slot within the range holding at least the given length
+    fn find_slot(
+        &self,
+        node: usize,
+        from: usize,
+        to: usize,
+        range: &Range<usize>,
+        min_length: u64,
+        leftmost: bool,
+    ) -> Option<usize> {
+        if to <= range.start || from >= range.end || self.tree[node] < min_length {
+            return None;
+        }
+
+        if to - from == 1 {
+            return Some(from);
+        }
+
+        let middle = (from + to) / 2;
+        let mut halves = [(2 * node, from, middle), (2 * node + 1, middle, to)];
+
+        if !leftmost {
+            halves.reverse();
+        }
+
+        halves.iter().find_map(|&(node, from, to)| {
+            self.find_slot(node, from, to, range, min_length, leftmost)
+        })
+    }
+
+    // Uses the first blocks of the span, returning where they start
+    fn take(&mut self, slot: usize, used_length: u64) -> u64 {
+        let free_span = self.spans[slot].clone();
+
+        self.set(
+            slot,
+            FreeSpan {
+                start: free_span.start + used_length,
+                length: free_span.length - used_length,
+            },
+        );
+
+        free_span.start
+    }
+
+    // Gives back the space a file left behind, joined with any free space around it
+    fn release(&mut self, start: u64, length: u64) {
+        if length == 0 {
+            return;
+        }
+
+        let mut slot = self
+            .slots_starts
+            .partition_point(|&slot_start| slot_start <= start)
+            - 1;
+
+        // Part of the same file might have already left the slot
+        let mut free_span = match self.spans[slot].length {
+            0 => FreeSpan { start, length },
+            _ => FreeSpan {
+                start: start.min(self.spans[slot].start),
+                length: length + self.spans[slot].length,
+            },
+        };
+
+        if let Some(previous_slot) = self.find_slot(1, 0, self.leaves, &(0..slot), 1, false) {
+            let previous_span = self.spans[previous_slot].clone();
+
+            if previous_span.start + previous_span.length == free_span.start {
+                self.set(slot, FreeSpan { start, length: 0 });
+
+                slot = previous_slot;
+                free_span = FreeSpan {
+                    start: previous_span.start,
+                    length: previous_span.length + free_span.length,
+                };
+            }
+        }
+
+        let next_slots = slot + 1..self.spans.len();
+
+        if let Some(next_slot) = self.find_slot(1, 0, self.leaves, &next_slots, 1, true) {
+            let next_span = self.spans[next_slot].clone();
+
+            if free_span.start + free_span.length == next_span.start {
+                self.set(
+                    next_slot,
+                    FreeSpan {
+                        start: next_span.start,
+                        length: 0,
+                    },
+                );
+
+                free_span.length += next_span.length;
+            }
+        }
+
+        self.set(slot, free_span);
+    }
+
+    fn set(&mut self, slot: usize, free_span: FreeSpan) {
+        self.by_length.remove(&(self.spans[slot].length, slot));
+        self.spans[slot] = free_span;
+        self.update(slot);
+    }
+
+    fn update(&mut self, slot: usize) {
+        let length = self.spans[slot].length;
+
+        if length > 0 {
+            self.by_length.insert((length, slot));
+        }
+
+        let mut node = self.leaves + slot;
+        self.tree[node] = length;
+
+        while node > 1 {
+            node /= 2;
+            self.tree[node] = self.tree[2 * node].max(self.tree[2 * node + 1]);
+        }
+    }
+}
+
+// Disk described by the spans each file takes, so blocks are never materialised.
+// The free space is whatever is left between files up to the disk size
+#[derive(Clone, Debug)]
+struct DiskSpans {
+    files: Vec<FileSpan>,
+    size: u64,
+}
+
+impl From<DiskMap> for DiskSpans {
     fn from(disk_map: DiskMap) -> Self {
-        let mut blocks_data = Vec::new();
+        let mut files = Vec::new();
 
         let mut file_index = 0;
+        let mut position = 0;
 
         for (i, &item) in disk_map.data.iter().enumerate() {
-            // Par numbers are file blocks, otherwise empty
-            let block_type = if i % 2 == 0 {
-                let file = DiskBlock::File { id: file_index };
+            let length = item as u64;
+
+            // Par numbers are file blocks, otherwise empty. Empty files only take an id
+            if i % 2 == 0 {
+                if length > 0 {
+                    files.push(FileSpan {
+                        id: file_index,
+                        start: position,
+                        length,
+                    });
+                }
 
                 file_index += 1;
+            }
 
-                file
-            } else {
-                DiskBlock::Empty
-            };
+            position += length;
+        }
+
+        Self {
+            files,
+            size: position,
+        }
+    }
+}
 
-            // As block as many times as the diskmap's item number
-            for _ in 0..item {
-                blocks_data.push(block_type.clone());
+impl From<DiskSpans> for DiskBlocks {
+    fn from(disk_spans: DiskSpans) -> Self {
+        let mut blocks_data = vec![DiskBlock::Empty; disk_spans.size as usize];
+
+        for file in disk_spans.files.iter() {
+            for i in file.start..file.start + file.length {
+                blocks_data[i as usize] = DiskBlock::File { id: file.id };
             }
         }
 
@@ -43,6 +384,11 @@ impl From<DiskMap> for DiskBlocks {
     }
 }
 
+impl From<DiskMap> for DiskBlocks {
+    fn from(disk_map: DiskMap) -> Self {
+        DiskSpans::from(disk_map).into()
+    }
+}
 impl From<DiskBlocks> for String {
     fn from(disk_blocks: DiskBlocks) -> Self {
         let mut blocks_data = String::new();
@@ -60,6 +406,186 @@ impl From<DiskBlocks> for String {
     }
 }
 
+impl TryFrom<DiskBlocks> for DiskMap {
+    type Error = DiskMapEncodingError;
+
+    fn try_from(disk_blocks: DiskBlocks) -> Result<Self, Self::Error> {
+        let mut data = Vec::new();
+
+        let mut next_file_id = 0;
+
+        for (block, length) in disk_blocks.get_runs() {
+            if length > 9 {
+                return Err(DiskMapEncodingError::RunTooLong);
+            }
+
+            match block {
+                DiskBlock::File { id } => {
+                    if id < next_file_id {
+                        return Err(DiskMapEncodingError::UnorderedFileIds);
+                    }
+
+                    // Skipped ids were empty files, and files always follow some free space
+                    if data.len() % 2 == 1 {
+                        data.push(0);
+                    }
+
+                    while next_file_id < id {
+                        data.extend([0, 0]);
+                        next_file_id += 1;
+                    }
+
+                    data.push(length as u32);
+                    next_file_id += 1;
+                }
+                DiskBlock::Empty => {
+                    // Free space can't be the first item, it needs an empty file before
+                    if data.len() % 2 == 0 {
+                        data.push(0);
+                        next_file_id += 1;
+                    }
+
+                    data.push(length as u32);
+                }
+            }
+        }
+
+        Ok(Self { data })
+    }
+}
+
+impl From<DiskMap> for String {
+    fn from(disk_map: DiskMap) -> Self {
+        disk_map.data.iter().map(|item| item.to_string()).collect()
+    }
+}
+
+impl From<DiskBlocks> for DiskSpans {
+    fn from(disk_blocks: DiskBlocks) -> Self {
+        let mut files = Vec::new();
+
+        let mut position = 0;
+
+        for (block, length) in disk_blocks.get_runs() {
+            if let DiskBlock::File { id } = block {
+                files.push(FileSpan {
+                    id,
+                    start: position,
+                    length: length as u64,
+                });
+            }
+
+            position += length as u64;
+        }
+
+        Self {
+            files,
+            size: position,
+        }
+    }
+}
+
+impl DiskBlocks {
+    fn get_runs(&self) -> Vec<(DiskBlock, usize)> {
+        let mut runs: Vec<(DiskBlock, usize)> = Vec::new();
+
+        for block in self.data.iter() {
+            match runs.last_mut() {
+                Some((last_block, length)) if last_block == block => *length += 1,
+                _ => runs.push((block.clone(), 1)),
+            }
+        }
+
+        runs
+    }
+
+    // Unlike the puzzle notation, every run is written as "id*length" or ".*length",
+    // so ids with several digits can be read back
+    #[allow(dead_code)]
+    fn to_dump(&self) -> String {
+        self.get_runs()
+            .iter()
+            .map(|(block, length)| match block {
+                DiskBlock::File { id } => format!("{}*{}", id, length),
+                DiskBlock::Empty => format!(".*{}", length),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    #[allow(dead_code)]
+    fn from_dump(dump: &str) -> Result<Self, DiskBlocksParsingError> {
+        let mut data = Vec::new();
+
+        for run in dump.split_whitespace() {
+            let (id, length) = run
+                .split_once('*')
+                .ok_or_else(|| DiskBlocksParsingError::MalformedRun(run.to_string()))?;
+
+            let block = match id {
+                "." => DiskBlock::Empty,
+                _ => DiskBlock::File {
+                    id: Self::parse_id(id)?,
+                },
+            };
+
+            let length: usize = length
+                .parse()
+                .map_err(|_| DiskBlocksParsingError::InvalidLength(length.to_string()))?;
+
+            data.extend(std::iter::repeat_n(block, length));
+        }
+
+        Ok(Self { data })
+    }
+
+    // Every block is an item of the array, empty blocks being null
+    #[allow(dead_code)]
+    fn to_json(&self) -> String {
+        let items = self
+            .data
+            .iter()
+            .map(|block| match block {
+                DiskBlock::File { id } => id.to_string(),
+                DiskBlock::Empty => String::from("null"),
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        format!("[{}]", items)
+    }
+
+    #[allow(dead_code)]
+    fn from_json(json: &str) -> Result<Self, DiskBlocksParsingError> {
+        let items = json
+            .trim()
+            .strip_prefix('[')
+            .and_then(|json| json.strip_suffix(']'))
+            .ok_or(DiskBlocksParsingError::NotAnArray)?;
+
+        if items.trim().is_empty() {
+            return Ok(Self { data: Vec::new() });
+        }
+
+        let data = items
+            .split(',')
+            .map(|item| match item.trim() {
+                "null" => Ok(DiskBlock::Empty),
+                item => Ok(DiskBlock::File {
+                    id: Self::parse_id(item)?,
+                }),
+            })
+            .collect::<Result<Vec<DiskBlock>, DiskBlocksParsingError>>()?;
+
+        Ok(Self { data })
+    }
+
+    fn parse_id(id: &str) -> Result<u32, DiskBlocksParsingError> {
+        id.parse()
+            .map_err(|_| DiskBlocksParsingError::InvalidId(id.to_string()))
+    }
+}
+
 impl DiskMap {
     fn parse(file_path: &str) -> Self {
         let mut file = File::open(file_path).expect("File can't be read");
@@ -78,142 +604,263 @@ impl DiskMap {
     }
 }
 
-impl DiskBlocks {
-    fn optimize_by_block(&mut self) {
-        // Move blocks from the right to the empty spaces on the left
+impl DiskSpans {
+    fn get_free_spans(&self) -> Vec<FreeSpan> {
+        let mut free_spans = Vec::new();
 
-        let mut left_index = 0;
-        let mut right_index = self.data.len() - 1;
+        let mut position = 0;
 
-        loop {
-            // Index from both sides until finding each pointers
-            if left_index == right_index {
-                break;
+        for file in self.files.iter() {
+            if file.start > position {
+                free_spans.push(FreeSpan {
+                    start: position,
+                    length: file.start - position,
+                });
             }
 
-            // Check we have a file on the right index
-            let item_right = &self.data[right_index];
+            position = file.start + file.length;
+        }
 
-            if item_right == &DiskBlock::Empty {
-                right_index -= 1;
-                continue;
-            }
+        if self.size > position {
+            free_spans.push(FreeSpan {
+                start: position,
+                length: self.size - position,
+            });
+        }
 
-            // Check we have an empty spot on the left index
-            let item_left = &self.data[left_index];
+        free_spans
+    }
 
-            if item_left != &DiskBlock::Empty {
-                left_index += 1;
-                continue;
-            }
+    fn optimize_by_block(&mut self) -> usize {
+        // Move blocks from the right to the empty spaces on the left
+        let mut free_spans: VecDeque<FreeSpan> = self.get_free_spans().into();
+        let mut optimized_files = Vec::with_capacity(self.files.len());
+        let mut moves = 0;
+
+        while let Some(mut file) = self.files.pop() {
+            // Fill the leftmost empty spaces with the last blocks of the file
+            while let Some(free_span) = free_spans.front_mut() {
+                if free_span.start >= file.start || file.length == 0 {
+                    break;
+                }
+
+                let moved_length = free_span.length.min(file.length);
 
-            // We have a match, perform transfer
-            let item_right = item_right.clone();
-            let item_left = item_left.clone();
+                optimized_files.push(FileSpan {
+                    id: file.id,
+                    start: free_span.start,
+                    length: moved_length,
+                });
 
-            self.data[right_index] = item_left;
-            self.data[left_index] = item_right;
+                free_span.start += moved_length;
+                free_span.length -= moved_length;
+                file.length -= moved_length;
+                moves += 1;
+
+                if free_span.length == 0 {
+                    free_spans.pop_front();
+                }
+            }
+
+            // Whatever wasn't moved stays in place
+            if file.length > 0 {
+                optimized_files.push(file);
+            }
         }
+
+        optimized_files.sort_by_key(|file| file.start);
+
+        self.files = optimized_files;
+
+        moves
     }
 
-    fn optimize_by_file(&mut self) {
-        // Move files from the right to the empty spaces on the left
-        let mut right_index = self.data.len() - 1;
+    fn optimize_by_file(&mut self) -> usize {
+        let (moves, _) = self.move_files(CompactionStrategy::FirstFit);
 
-        loop {
-            // Once right index reaches the left side, no more files to scan
-            if right_index == 0 {
-                break;
-            }
+        moves
+    }
+
+    // Returns how many files, or fragments, were moved, and how many files couldn't be
+    fn move_files(&mut self, strategy: CompactionStrategy) -> (usize, usize) {
+        let mut free_spans = FreeSpansIndex::from(&*self);
+        let mut moves = 0;
+        let mut failed_moves = 0;
+
+        // Move files from the right to the empty spaces on the left, by decreasing id
+        let mut files_order: Vec<usize> = (0..self.files.len()).collect();
+        files_order.sort_by_key(|&i| Reverse((self.files[i].id, self.files[i].start)));
+
+        for i in files_order {
+            let file = &mut self.files[i];
 
-            // Check if we have a file on the right index
-            let item_right = &self.data[right_index];
+            // Space left behind is only used by files still further to the right
+            if let Some(slot) = free_spans.find(file.length, file.start, strategy) {
+                let previous_start = file.start;
 
-            if item_right == &DiskBlock::Empty {
-                right_index -= 1;
+                file.start = free_spans.take(slot, file.length);
+                free_spans.release(previous_start, file.length);
+                moves += 1;
                 continue;
             }
 
-            // Check if the same item is repeated one after another (file)
-            let mut item_right_size = 1;
-            while &self.data[right_index - item_right_size] == item_right {
-                item_right_size += 1;
+            if strategy == CompactionStrategy::SplitWhenNeeded {
+                moves += self.split_file(i, &mut free_spans);
+                continue;
+            }
 
-                // Stop if we are reaching negative offset
-                if item_right_size > right_index {
-                    break;
-                }
+            // There was free space on the left, but none of it was big enough
+            if free_spans
+                .find(1, file.start, CompactionStrategy::FirstFit)
+                .is_some()
+            {
+                failed_moves += 1;
             }
+        }
 
-            // Check for empty spots from the left side up to the right index
-            let mut left_index = 0;
+        self.files.retain(|file| file.length > 0);
+        self.files.sort_by_key(|file| file.start);
 
-            while left_index < right_index {
-                let item_left = &self.data[left_index];
+        (moves, failed_moves)
+    }
 
-                if item_left != &DiskBlock::Empty {
-                    left_index += 1;
-                    continue;
-                }
+    // The file doesn't fit whole, so its last blocks fill the leftmost spaces.
+    // Once the remaining part fits whole, it's moved at once. Returns how many
+    // fragments were moved
+    fn split_file(&mut self, i: usize, free_spans: &mut FreeSpansIndex) -> usize {
+        let file = &mut self.files[i];
+        let mut fragments = Vec::new();
+        let mut moves = 0;
+
+        while file.length > 0 {
+            if let Some(slot) =
+                free_spans.find(file.length, file.start, CompactionStrategy::FirstFit)
+            {
+                let previous_start = file.start;
+
+                file.start = free_spans.take(slot, file.length);
+                free_spans.release(previous_start, file.length);
+                moves += 1;
+                break;
+            }
 
-                // Check for empty spaces one after another
-                let mut item_left_size = 1;
-                while &self.data[left_index + item_left_size] == item_left {
-                    item_left_size += 1;
-                }
+            let Some(slot) = free_spans.find(1, file.start, CompactionStrategy::FirstFit) else {
+                break;
+            };
 
-                // Check if space is enough. Otherwise, skip all the spaces and keep searching
-                if item_left_size < item_right_size {
-                    left_index += item_left_size;
-                    continue;
-                }
+            let moved_length = free_spans.get(slot).length.min(file.length);
 
-                // Match found, perform transfer
-                for i in 0..item_right_size {
-                    let item_left_tmp = self.data[left_index + i].clone();
-                    let item_right_tmp = self.data[right_index - i].clone();
+            fragments.push(FileSpan {
+                id: file.id,
+                start: free_spans.take(slot, moved_length),
+                length: moved_length,
+            });
+            file.length -= moved_length;
+            free_spans.release(file.start + file.length, moved_length);
+            moves += 1;
+        }
 
-                    self.data[left_index + i] = item_right_tmp;
-                    self.data[right_index - i] = item_left_tmp;
-                }
+        self.files.extend(fragments);
 
-                break;
-            }
+        moves
+    }
 
-            // Try to move right index to the next file, otherwise no more to scan
-            if item_right_size > right_index {
-                break;
-            }
-            right_index -= item_right_size;
+    fn compact(&mut self, strategy: CompactionStrategy) -> CompactionReport {
+        let (moves, failed_moves) = match strategy {
+            CompactionStrategy::Block => (self.optimize_by_block(), 0),
+            CompactionStrategy::FirstFit
+            | CompactionStrategy::BestFit
+            | CompactionStrategy::WorstFit
+            | CompactionStrategy::SplitWhenNeeded => self.move_files(strategy),
+        };
+
+        CompactionReport {
+            checksum: self.get_checksum(),
+            moves,
+            failed_moves,
+            stats: self.get_stats(),
         }
     }
 
-    fn get_checksum(&self) -> u64 {
-        let mut checksum = 0;
+    fn get_stats(&self) -> DiskStats {
+        let free_spans = self.get_free_spans();
+
+        let used_blocks = self.files.iter().map(|file| file.length).sum();
+
+        // The free space at the end of the disk isn't a gap
+        let free_gaps = free_spans
+            .iter()
+            .filter(|free_span| free_span.start + free_span.length < self.size)
+            .count();
+
+        let largest_free_run = free_spans
+            .iter()
+            .map(|free_span| free_span.length)
+            .max()
+            .unwrap_or_default();
+
+        let mut file_fragments: BTreeMap<u32, usize> = BTreeMap::new();
+
+        // Adjacent spans of the same file are still a single fragment
+        let mut last_file: Option<&FileSpan> = None;
+
+        for file in self.files.iter() {
+            let is_contiguous = last_file.is_some_and(|last_file| {
+                last_file.id == file.id && last_file.start + last_file.length == file.start
+            });
 
-        for (i, item) in self.data.iter().enumerate() {
-            if let DiskBlock::File { id } = item {
-                checksum += *id as u64 * i as u64;
+            if !is_contiguous {
+                *file_fragments.entry(file.id).or_default() += 1;
             }
+
+            last_file = Some(file);
+        }
+
+        DiskStats {
+            used_blocks,
+            free_blocks: self.size - used_blocks,
+            free_gaps,
+            largest_free_run,
+            file_fragments,
         }
+    }
 
-        checksum
+    fn get_checksum(&self) -> u64 {
+        self.files
+            .iter()
+            .map(|file| {
+                // Sum of the positions from start to start + length - 1
+                let positions_sum =
+                    file.start * file.length + file.length * (file.length.saturating_sub(1)) / 2;
+
+                file.id as u64 * positions_sum
+            })
+            .sum()
     }
 }
 
 fn main() {
     let disk_map = DiskMap::parse("day9/data/input.txt");
 
-    let mut disk_blocks: DiskBlocks = disk_map.into();
+    let disk_spans: DiskSpans = disk_map.into();
+
+    let mut optimized_disk_spans = disk_spans.clone();
+    optimized_disk_spans.optimize_by_block();
 
-    let mut optimized_disk_blockes = disk_blocks.clone();
-    optimized_disk_blockes.optimize_by_block();
+    println!("Part 1 result: {}", optimized_disk_spans.get_checksum());
 
-    println!("Part 1 result: {}", optimized_disk_blockes.get_checksum());
+    let mut optimized_disk_spans = disk_spans.clone();
+    optimized_disk_spans.optimize_by_file();
 
-    disk_blocks.optimize_by_file();
+    println!("Part 2 result: {}", optimized_disk_spans.get_checksum());
 
-    println!("Part 2 result: {}", disk_blocks.get_checksum());
+    // Every strategy compacts its own copy of the same disk
+    for strategy in CompactionStrategy::generate_strategies_list() {
+        let mut compacted_disk_spans = disk_spans.clone();
+        let report = compacted_disk_spans.compact(strategy);
+
+        println!("{:?} compaction: {}", strategy, report);
+    }
 }
 
 #[cfg(test)]
@@ -252,16 +899,16 @@ mod tests {
     fn check_disk_block_optimized_by_block_checksum() {
         let input = get_test_input();
 
-        let mut disk_blocks: DiskBlocks = input.into();
+        let mut disk_spans: DiskSpans = input.into();
 
-        disk_blocks.optimize_by_block();
+        disk_spans.optimize_by_block();
 
         assert_eq!(
-            String::from(disk_blocks.clone()),
+            String::from(DiskBlocks::from(disk_spans.clone())),
             String::from("0099811188827773336446555566..............")
         );
 
-        let checksum = disk_blocks.get_checksum();
+        let checksum = disk_spans.get_checksum();
 
         assert_eq!(checksum, 1928);
     }
@@ -270,17 +917,344 @@ mod tests {
     fn check_disk_block_optimized_by_file_checksum() {
         let input = get_test_input();
 
-        let mut disk_blocks: DiskBlocks = input.into();
+        let mut disk_spans: DiskSpans = input.into();
 
-        disk_blocks.optimize_by_file();
+        disk_spans.optimize_by_file();
 
         assert_eq!(
-            String::from(disk_blocks.clone()),
+            String::from(DiskBlocks::from(disk_spans.clone())),
             String::from("00992111777.44.333....5555.6666.....8888..")
         );
 
-        let checksum = disk_blocks.get_checksum();
+        let checksum = disk_spans.get_checksum();
 
         assert_eq!(checksum, 2858);
     }
+
+    #[test]
+    fn check_disk_map_to_disk_spans() {
+        let input = DiskMap {
+            data: vec![1, 2, 0, 3, 2],
+        };
+
+        let disk_spans: DiskSpans = input.into();
+
+        assert_eq!(disk_spans.size, 8);
+        assert_eq!(
+            disk_spans.files,
+            vec![
+                FileSpan {
+                    id: 0,
+                    start: 0,
+                    length: 1
+                },
+                FileSpan {
+                    id: 2,
+                    start: 6,
+                    length: 2
+                },
+            ]
+        );
+        assert_eq!(
+            disk_spans.get_free_spans(),
+            vec![FreeSpan {
+                start: 1,
+                length: 5
+            }]
+        );
+    }
+
+    #[test]
+    fn check_large_disk_spans() {
+        let length = 1_000_000_000;
+
+        let mut disk_spans = DiskSpans {
+            files: vec![
+                FileSpan {
+                    id: 0,
+                    start: 0,
+                    length,
+                },
+                FileSpan {
+                    id: 1,
+                    start: 3 * length,
+                    length,
+                },
+            ],
+            size: 4 * length,
+        };
+
+        disk_spans.optimize_by_file();
+
+        assert_eq!(disk_spans.files[1].start, length);
+        assert_eq!(disk_spans.get_free_spans()[0].length, 2 * length);
+
+        let positions_sum = (2 * length - 1) * 2 * length / 2 - (length - 1) * length / 2;
+        assert_eq!(disk_spans.get_checksum(), positions_sum);
+    }
+
+    #[test]
+    fn check_distinct_lengths_first_fit() {
+        // Files and gaps of mostly distinct lengths, scattered all over the disk
+        let mut files = Vec::new();
+        let mut position = 0;
+
+        for id in 0..2000 {
+            let length = (id * 104729) % 5000 + 1;
+
+            files.push(FileSpan {
+                id: id as u32,
+                start: position,
+                length,
+            });
+
+            position += length + (id * 7919) % 6000;
+        }
+
+        let mut disk_spans = DiskSpans {
+            files: files.clone(),
+            size: position,
+        };
+
+        disk_spans.optimize_by_file();
+
+        // Same moves done by checking every free span in order
+        let mut free_spans = DiskSpans {
+            files: files.clone(),
+            size: position,
+        }
+        .get_free_spans();
+
+        for file in files.iter_mut().rev() {
+            let Some(free_span) = free_spans
+                .iter_mut()
+                .find(|free_span| free_span.length >= file.length && free_span.start < file.start)
+            else {
+                continue;
+            };
+
+            file.start = free_span.start;
+            free_span.start += file.length;
+            free_span.length -= file.length;
+        }
+
+        files.sort_by_key(|file| file.start);
+
+        assert_eq!(disk_spans.files, files);
+    }
+
+    #[test]
+    fn check_unordered_file_ids() {
+        // Files further to the right can use the space left behind by the previous ones
+        let mut disk_spans = DiskSpans::from(DiskBlocks::from_dump(".*2 1*2 0*2").unwrap());
+        disk_spans.optimize_by_file();
+
+        assert_eq!(
+            String::from(DiskBlocks::from(disk_spans)),
+            String::from("1100..")
+        );
+
+        // The space left behind is merged with the free spans around it
+        let mut disk_spans = DiskSpans::from(DiskBlocks::from_dump(".*1 2*1 .*1 1*2 0*2").unwrap());
+        disk_spans.optimize_by_file();
+
+        assert_eq!(
+            String::from(DiskBlocks::from(disk_spans)),
+            String::from("21100..")
+        );
+    }
+
+    #[test]
+    fn check_compaction_strategies() {
+        let disk_spans: DiskSpans = get_test_input().into();
+
+        let mut compacted_disk = disk_spans.clone();
+        let report = compacted_disk.compact(CompactionStrategy::FirstFit);
+
+        assert_eq!(report.checksum, 2858);
+        assert_eq!(report.moves, 4);
+        assert_eq!(report.failed_moves, 4);
+        assert_eq!(report.stats.free_gaps, 5);
+        assert_eq!(report.stats.get_fragmented_files_count(), 0);
+
+        let mut compacted_disk = disk_spans.clone();
+        let report = compacted_disk.compact(CompactionStrategy::Block);
+
+        assert_eq!(report.checksum, 1928);
+        assert_eq!(report.failed_moves, 0);
+        assert_eq!(report.stats.free_gaps, 0);
+
+        // Best fit prefers the exact span over the leftmost one
+        let disk_spans: DiskSpans = DiskMap {
+            data: vec![1, 3, 1, 2, 2],
+        }
+        .into();
+
+        let mut compacted_disk = disk_spans.clone();
+        compacted_disk.compact(CompactionStrategy::FirstFit);
+
+        assert_eq!(
+            String::from(DiskBlocks::from(compacted_disk)),
+            String::from("0221.....")
+        );
+
+        let mut compacted_disk = disk_spans.clone();
+        compacted_disk.compact(CompactionStrategy::BestFit);
+
+        assert_eq!(
+            String::from(DiskBlocks::from(compacted_disk)),
+            String::from("01...22..")
+        );
+
+        // Worst fit prefers the biggest span over the leftmost one
+        let mut compacted_disk: DiskSpans = DiskMap {
+            data: vec![1, 2, 1, 4, 2],
+        }
+        .into();
+        compacted_disk.compact(CompactionStrategy::WorstFit);
+
+        assert_eq!(
+            String::from(DiskBlocks::from(compacted_disk)),
+            String::from("01..22....")
+        );
+
+        let disk_spans: DiskSpans = get_test_input().into();
+
+        let mut compacted_disk = disk_spans.clone();
+        let report = compacted_disk.compact(CompactionStrategy::SplitWhenNeeded);
+
+        assert_eq!(
+            String::from(DiskBlocks::from(compacted_disk)),
+            String::from("0099811188827773336446555566..............")
+        );
+        assert_eq!(report.stats.get_fragmented_files_count(), 2);
+        assert_eq!(report.stats.file_fragments.get(&8), Some(&2));
+    }
+
+    #[test]
+    fn check_disk_blocks_dump() {
+        let input = DiskMap {
+            data: vec![
+                1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2,
+            ],
+        };
+
+        let mut disk_spans: DiskSpans = input.into();
+        disk_spans.optimize_by_file();
+
+        let disk_blocks = DiskBlocks::from(disk_spans.clone());
+
+        let dump = disk_blocks.to_dump();
+
+        assert_eq!(
+            dump,
+            "0*1 10*1 1*1 9*1 2*1 8*1 3*1 7*1 4*1 6*1 5*1 .*11 11*2"
+        );
+        assert_eq!(DiskBlocks::from_dump(&dump), Ok(disk_blocks.clone()));
+
+        let json = disk_blocks.to_json();
+
+        assert!(json.starts_with("[0,10,1,9,2,8,"));
+        assert!(json.ends_with(",null,11,11]"));
+        assert_eq!(DiskBlocks::from_json(&json), Ok(disk_blocks));
+
+        // Reloaded disks keep the same checksum
+        assert_eq!(
+            DiskSpans::from(DiskBlocks::from_dump(&dump).unwrap()).get_checksum(),
+            disk_spans.get_checksum()
+        );
+
+        assert_eq!(
+            DiskBlocks::from_dump("0*1 1"),
+            Err(DiskBlocksParsingError::MalformedRun(String::from("1")))
+        );
+        assert_eq!(
+            DiskBlocks::from_dump("a*1"),
+            Err(DiskBlocksParsingError::InvalidId(String::from("a")))
+        );
+        assert_eq!(
+            DiskBlocks::from_dump(".*-1"),
+            Err(DiskBlocksParsingError::InvalidLength(String::from("-1")))
+        );
+        assert_eq!(DiskBlocks::from_json("[]"), Ok(DiskBlocks { data: vec![] }));
+        assert_eq!(
+            DiskBlocks::from_json("0,null"),
+            Err(DiskBlocksParsingError::NotAnArray)
+        );
+        assert_eq!(
+            DiskBlocks::from_json("[0,,1]"),
+            Err(DiskBlocksParsingError::InvalidId(String::new()))
+        );
+    }
+
+    #[test]
+    fn check_disk_blocks_to_disk_map() {
+        let disk_blocks: DiskBlocks = get_test_input().into();
+
+        let disk_map = DiskMap::try_from(disk_blocks).expect("Disk map can't be encoded");
+
+        assert_eq!(String::from(disk_map), String::from("2333133121414131402"));
+
+        // Adjacent runs of the same file are merged, and skipped ids become empty files
+        let disk_blocks = DiskBlocks::from_dump("0*2 0*1 .*2 2*1").unwrap();
+
+        assert_eq!(
+            DiskMap::try_from(disk_blocks),
+            Ok(DiskMap {
+                data: vec![3, 2, 0, 0, 1]
+            })
+        );
+
+        let mut disk_spans: DiskSpans = get_test_input().into();
+        disk_spans.optimize_by_file();
+
+        assert_eq!(
+            DiskMap::try_from(DiskBlocks::from(disk_spans)),
+            Err(DiskMapEncodingError::UnorderedFileIds)
+        );
+
+        assert_eq!(
+            DiskMap::try_from(DiskBlocks::from_dump("0*10").unwrap()),
+            Err(DiskMapEncodingError::RunTooLong)
+        );
+    }
+
+    #[test]
+    fn check_disk_stats() {
+        let mut disk_spans: DiskSpans = get_test_input().into();
+
+        let stats = disk_spans.get_stats();
+
+        assert_eq!(stats.used_blocks, 28);
+        assert_eq!(stats.free_blocks, 14);
+        assert_eq!(stats.free_gaps, 8);
+        assert_eq!(stats.largest_free_run, 3);
+        assert_eq!(stats.file_fragments.len(), 10);
+
+        disk_spans.optimize_by_file();
+
+        assert_eq!(
+            disk_spans.get_stats(),
+            DiskStats {
+                used_blocks: 28,
+                free_blocks: 14,
+                free_gaps: 5,
+                largest_free_run: 5,
+                file_fragments: (0..10).map(|id| (id, 1)).collect(),
+            }
+        );
+
+        // Block compaction splits files, but leaves no gaps
+        let mut disk_spans: DiskSpans = get_test_input().into();
+        disk_spans.optimize_by_block();
+
+        let stats = disk_spans.get_stats();
+
+        assert_eq!(stats.free_gaps, 0);
+        assert_eq!(stats.largest_free_run, 14);
+        assert_eq!(stats.file_fragments.get(&9), Some(&1));
+        assert_eq!(stats.file_fragments.get(&8), Some(&2));
+        assert_eq!(stats.file_fragments.get(&6), Some(&3));
+        assert_eq!(stats.get_fragmented_files_count(), 2);
+    }
 }